@@ -0,0 +1,353 @@
+use std::{io::Read, process::ExitCode};
+
+use gcad::{
+    export::{self, SvgOptions},
+    parse::{self, Diagnostic, Figure, Path},
+    solver::{Number, Vector},
+};
+
+const USAGE: &str = "usage: gcad-cli solve [--format text|json|csv] [FILE]
+       gcad-cli svg [--stroke-width N] [--margin N] [--no-points]
+                    [--no-labels] [--no-axes] [--no-construction] [FILE]
+
+`solve` prints the position of every point in the order the points are
+first used. `svg` prints the figure as an SVG document. Both read standard
+input when FILE is `-` or missing.";
+
+/// What to print.
+#[derive(Debug, Clone, PartialEq)]
+enum Command {
+    Solve(Format),
+    Svg(SvgOptions),
+}
+
+/// How solved positions are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// `A = (x, y)` per point, as the document would place it.
+    Text,
+    Json,
+    /// A `point,x,y` header and a row per point.
+    Csv,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if matches!(args.first().map(String::as_str), Some("-h" | "--help")) {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let (command, file) = match parse_args(&args) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let (name, document) = match read(file) {
+        Ok(read) => read,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let (solution, paths, diagnostics) = solve(&document);
+    for diagnostic in &diagnostics {
+        eprint!("{}", report(&name, &document, diagnostic));
+    }
+    match command {
+        Command::Solve(format) => print!("{}", format_solution(&solution, format)),
+        Command::Svg(options) => {
+            let solution = solution.into_iter().collect();
+            print!("{}", export::svg(&solution, &paths, &options));
+        }
+    }
+    if diagnostics.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+/// The command and input file from the arguments.
+fn parse_args(args: &[String]) -> Result<(Command, Option<&str>), String> {
+    let mut args = args.iter().map(String::as_str);
+    let mut command = match args.next() {
+        Some("solve") => Command::Solve(Format::Text),
+        Some("svg") => Command::Svg(SvgOptions::default()),
+        Some(command) => return Err(format!("unknown command `{command}`")),
+        None => return Err("missing command".to_string()),
+    };
+    let mut file = None;
+    while let Some(arg) = args.next() {
+        match (&mut command, arg) {
+            (Command::Solve(format), "-f" | "--format") => {
+                *format = match args.next() {
+                    Some("text") => Format::Text,
+                    Some("json") => Format::Json,
+                    Some("csv") => Format::Csv,
+                    Some(other) => return Err(format!("unknown format `{other}`")),
+                    None => return Err(format!("missing format after `{arg}`")),
+                }
+            }
+            (Command::Svg(options), "--stroke-width") => {
+                options.stroke_width = Some(number(arg, args.next())?)
+            }
+            (Command::Svg(options), "--margin") => options.margin = Some(number(arg, args.next())?),
+            (Command::Svg(options), "--no-points") => options.points = false,
+            (Command::Svg(options), "--no-labels") => options.labels = false,
+            (Command::Svg(options), "--no-axes") => options.axes = false,
+            (Command::Svg(options), "--no-construction") => options.construction = false,
+            _ if file.is_some() || (arg.starts_with('-') && arg != "-") => {
+                return Err(format!("unexpected argument `{arg}`"))
+            }
+            _ => file = Some(arg),
+        }
+    }
+    Ok((command, file))
+}
+
+/// The number following an option.
+fn number(option: &str, value: Option<&str>) -> Result<Number, String> {
+    let value = value.ok_or_else(|| format!("missing number after `{option}`"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid number `{value}` after `{option}`"))
+}
+
+/// The name to report and the contents of the file, or of standard input.
+fn read(file: Option<&str>) -> Result<(String, String), String> {
+    match file {
+        None | Some("-") => {
+            let mut document = String::new();
+            std::io::stdin()
+                .read_to_string(&mut document)
+                .map_err(|e| format!("cannot read standard input: {e}"))?;
+            Ok(("<stdin>".to_string(), document))
+        }
+        Some(path) => std::fs::read_to_string(path)
+            .map(|document| (path.to_string(), document))
+            .map_err(|e| format!("cannot read `{path}`: {e}")),
+    }
+}
+
+/// The placed position of every point in the order they are first used,
+/// the paths and every diagnostic. Lines with errors are skipped as in the
+/// app.
+fn solve(document: &str) -> (Vec<(String, Vector)>, Vec<Path>, Vec<Diagnostic>) {
+    let (statements, mut diagnostics) = parse::parse(document);
+    let mut points: Vec<String> = Vec::new();
+    for point in statements.iter().flat_map(|s| s.points()) {
+        if !points.contains(point) {
+            points.push(point.clone());
+        }
+    }
+    let fig = match Figure::from_statements(statements) {
+        Ok(fig) => fig,
+        Err(errors) => {
+            diagnostics.extend(errors.into_iter().map(Diagnostic::from));
+            return (Vec::new(), Vec::new(), diagnostics);
+        }
+    };
+    let mut pos = match fig.solve() {
+        Ok(pos) => pos,
+        Err(e) => {
+            diagnostics.push(e);
+            return (Vec::new(), Vec::new(), diagnostics);
+        }
+    };
+    diagnostics.extend(fig.unsatisfied(&pos));
+    diagnostics.extend(fig.conflicts(&pos));
+    fig.place(&mut pos);
+    let solution = points
+        .into_iter()
+        .filter_map(|point| {
+            let i = *fig.point_map.get(&point)?;
+            Some((point, pos[i]))
+        })
+        .collect();
+    (solution, fig.paths, diagnostics)
+}
+
+/// A diagnostic and its notes as `file:line:col: message` lines.
+fn report(name: &str, document: &str, diagnostic: &Diagnostic) -> String {
+    let (line, col) = diagnostic.span.line_col(document);
+    let mut report = format!("{name}:{line}:{col}: error: {}\n", diagnostic.message);
+    for note in &diagnostic.notes {
+        match note.span {
+            Some(span) => {
+                let (line, col) = span.line_col(document);
+                report += &format!("{name}:{line}:{col}: note: {}\n", note.message);
+            }
+            None => report += &format!("  note: {}\n", note.message),
+        }
+    }
+    report
+}
+
+fn format_solution(solution: &[(String, Vector)], format: Format) -> String {
+    let mut out = String::new();
+    match format {
+        Format::Text => {
+            for (point, v) in solution {
+                out += &format!("{point} = {v}\n");
+            }
+        }
+        Format::Json => {
+            let points: Vec<String> = solution
+                .iter()
+                .map(|(point, v)| {
+                    format!(
+                        "{{\"point\": {}, \"x\": {}, \"y\": {}}}",
+                        json_string(point),
+                        json_number(v.x),
+                        json_number(v.y)
+                    )
+                })
+                .collect();
+            out += &format!("[{}]\n", points.join(", "));
+        }
+        Format::Csv => {
+            out += "point,x,y\n";
+            for (point, v) in solution {
+                out += &format!("{},{},{}\n", csv_field(point), v.x, v.y);
+            }
+        }
+    }
+    out
+}
+
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            c if c.is_control() => quoted += &format!("\\u{:04x}", c as u32),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// JSON has no NaN or infinity, so they become `null`.
+fn json_number(n: Number) -> String {
+    if n.is_finite() {
+        n.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn arguments_choose_the_command() {
+        let a = args("solve --format json fig.gcad");
+        assert_eq!(
+            parse_args(&a),
+            Ok((Command::Solve(Format::Json), Some("fig.gcad")))
+        );
+        let a = args("solve -");
+        assert_eq!(
+            parse_args(&a),
+            Ok((Command::Solve(Format::Text), Some("-")))
+        );
+        let a = args("svg --stroke-width 0.5 --margin 2 --no-axes --no-construction");
+        let options = SvgOptions {
+            stroke_width: Some(0.5),
+            margin: Some(2.),
+            axes: false,
+            construction: false,
+            ..Default::default()
+        };
+        assert_eq!(parse_args(&a), Ok((Command::Svg(options), None)));
+    }
+
+    #[test]
+    fn bad_arguments_are_reported() {
+        for (a, message) in [
+            ("", "missing command"),
+            ("draw", "unknown command `draw`"),
+            ("solve --format xml", "unknown format `xml`"),
+            ("solve --format", "missing format after `--format`"),
+            ("svg --margin", "missing number after `--margin`"),
+            (
+                "svg --margin wide",
+                "invalid number `wide` after `--margin`",
+            ),
+            ("solve --no-axes", "unexpected argument `--no-axes`"),
+            ("solve a.gcad b.gcad", "unexpected argument `b.gcad`"),
+        ] {
+            assert_eq!(parse_args(&args(a)), Err(message.to_string()), "{a}");
+        }
+    }
+
+    #[test]
+    fn solutions_are_formatted() {
+        let solution = [
+            ("A".to_string(), Vector { x: 0., y: 1.5 }),
+            ("B′".to_string(), Vector { x: -2., y: 0. }),
+        ];
+        assert_eq!(
+            format_solution(&solution, Format::Text),
+            "A = (0, 1.5)\nB′ = (-2, 0)\n"
+        );
+        assert_eq!(
+            format_solution(&solution, Format::Json),
+            "[{\"point\": \"A\", \"x\": 0, \"y\": 1.5}, {\"point\": \"B′\", \"x\": -2, \"y\": 0}]\n"
+        );
+        assert_eq!(
+            format_solution(&solution, Format::Csv),
+            "point,x,y\nA,0,1.5\nB′,-2,0\n"
+        );
+        assert_eq!(format_solution(&[], Format::Json), "[]\n");
+        assert_eq!(format_solution(&[], Format::Csv), "point,x,y\n");
+    }
+
+    #[test]
+    fn json_is_escaped() {
+        assert_eq!(json_string("A"), "\"A\"");
+        assert_eq!(json_string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(json_string("a\nb\u{1}"), "\"a\\u000ab\\u0001\"");
+        assert_eq!(json_number(0.25), "0.25");
+        for n in [Number::NAN, Number::INFINITY, Number::NEG_INFINITY] {
+            assert_eq!(json_number(n), "null");
+        }
+        let solution = [(
+            "A".to_string(),
+            Vector {
+                x: Number::NAN,
+                y: 1.,
+            },
+        )];
+        assert_eq!(
+            format_solution(&solution, Format::Json),
+            "[{\"point\": \"A\", \"x\": null, \"y\": 1}]\n"
+        );
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("A"), "A");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    parse::{Path, Segment, PRIMES},
+    solver::{Number, Vector},
+};
+
+#[cfg(test)]
+mod tests;
+
+/// What [svg] draws and how.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// Width of the drawn paths, in figure units. Defaults to a width
+    /// relative to the size of the figure.
+    pub stroke_width: Option<Number>,
+    /// Space around the points, in figure units. Defaults to the margin of
+    /// [bounding_box].
+    pub margin: Option<Number>,
+    /// Draws a marker at each point.
+    pub points: bool,
+    /// Names each point.
+    pub labels: bool,
+    /// Draws the x and y axes.
+    pub axes: bool,
+    /// Shows points which are not on any path.
+    pub construction: bool,
+}
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            stroke_width: None,
+            margin: None,
+            points: true,
+            labels: true,
+            axes: true,
+            construction: true,
+        }
+    }
+}
+
+/// A self-contained SVG document of the solved points and paths, drawn as
+/// the app draws them.
+pub fn svg(solution: &HashMap<String, Vector>, paths: &[Path], options: &SvgOptions) -> String {
+    let drawn: HashSet<&String> = paths.iter().flat_map(Path::points).collect();
+    let mut shown: Vec<(&String, Vector)> = solution
+        .iter()
+        .filter(|(point, _)| options.construction || drawn.contains(point))
+        .map(|(point, v)| (point, *v))
+        .collect();
+    shown.sort_by(|a, b| a.0.cmp(b.0));
+    let (min, size) =
+        bounding_box_with_margin(shown.iter().map(|(_, v)| *v), options.margin).unwrap_or_default();
+    let font_size = size.y / 30.;
+    let stroke_width = options.stroke_width.unwrap_or(font_size / 12.);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+        min.x, min.y, size.x, size.y, size.x, size.y
+    );
+    if options.axes {
+        svg += "<defs><marker id=\"triangle\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" \
+                markerUnits=\"strokeWidth\" markerWidth=\"10\" markerHeight=\"10\" orient=\"auto\">\
+                <path d=\"M 0 0 L 10 5 L 0 10 z\" fill=\"gray\"/></marker></defs>\n";
+        let axis = |x1, y1, x2, y2| {
+            format!(
+                "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"gray\" \
+                 stroke-width=\"{}\" marker-end=\"url(#triangle)\"/>\n",
+                font_size / 24.
+            )
+        };
+        svg += &axis(0., min.y, 0., min.y + size.y);
+        svg += &axis(min.x, 0., min.x + size.x, 0.);
+    }
+    for path in paths {
+        svg += &format!(
+            "<path d=\"{}\" fill=\"none\" stroke=\"black\" stroke-width=\"{stroke_width}\"/>\n",
+            path_data(path, solution)
+        );
+    }
+    for (point, v) in shown {
+        if options.points {
+            svg += &format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"black\"/>\n",
+                v.x,
+                v.y,
+                font_size / 6.
+            );
+        }
+        if options.labels {
+            let (base, sub, primes) = label(point);
+            let sub = if sub.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "<tspan baseline-shift=\"sub\" font-size=\"70%\">{}</tspan>",
+                    escape(&sub)
+                )
+            };
+            svg += &format!(
+                "<text x=\"{}\" y=\"{}\" font-style=\"italic\" font-size=\"{font_size}\" \
+                 font-family=\"sans-serif\">{}{sub}{}</text>\n",
+                v.x,
+                v.y - font_size / 2.,
+                escape(&base),
+                escape(&primes)
+            );
+        }
+    }
+    svg += "</svg>\n";
+    svg
+}
+
+/// Escapes text for XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Splits a point name into its base, subscript and primes
+/// (e.g. `A_2'` into `A`, `2` and `′`).
+pub fn label(point: &str) -> (String, String, String) {
+    let name = point.trim_end_matches(&PRIMES[..]);
+    let primes = point[name.len()..]
+        .chars()
+        .map(|c| if c == '\'' { '′' } else { c })
+        .collect();
+    let (base, sub) = name.split_once('_').unwrap_or((name, ""));
+    (base.to_string(), sub.to_string(), primes)
+}
+
+/// The SVG path data drawing a path through the solved points.
+pub fn path_data(path: &Path, solution: &HashMap<String, Vector>) -> String {
+    let pos = |point: &String| {
+        let v = solution[point];
+        format!("{} {}", v.x, v.y)
+    };
+    let mut d = format!("M {}", pos(&path.start));
+    for segment in &path.segments {
+        d += &match segment {
+            Segment::Line(p0) => format!(" L {}", pos(p0)),
+            Segment::Quadratic(p0, p1) => format!(" Q {} {}", pos(p0), pos(p1)),
+            Segment::Cubic(p0, p1, p2) => format!(" C {} {} {}", pos(p0), pos(p1), pos(p2)),
+        };
+    }
+    if path.is_closed() {
+        d += " Z";
+    }
+    d
+}
+
+/// The corner and size of a box around the positions with a margin.
+pub fn bounding_box(pos: impl Iterator<Item = Vector>) -> Option<(Vector, Vector)> {
+    bounding_box_with_margin(pos, None)
+}
+
+/// [bounding_box] with a given margin instead of one relative to the size
+/// of the positions.
+pub fn bounding_box_with_margin(
+    mut pos: impl Iterator<Item = Vector>,
+    margin: Option<Number>,
+) -> Option<(Vector, Vector)> {
+    let mut min = pos.next()?;
+    let mut max = min;
+    for p in pos {
+        min.x = min.x.min(p.x);
+        min.y = min.y.min(p.y);
+        max.x = max.x.max(p.x);
+        max.y = max.y.max(p.y);
+    }
+    let size = max - min;
+    let margin = margin.unwrap_or((size.x.max(size.y) * 0.25).max(3.));
+    min.x -= margin;
+    min.y -= margin;
+    max.x += margin;
+    max.y += margin;
+    Some((min, max - min))
+}
@@ -0,0 +1,95 @@
+use super::*;
+
+fn solution(points: &[(&str, Number, Number)]) -> HashMap<String, Vector> {
+    points
+        .iter()
+        .map(|&(point, x, y)| (point.to_string(), Vector { x, y }))
+        .collect()
+}
+
+fn path(start: &str, segments: &[Segment]) -> Path {
+    Path {
+        start: start.to_string(),
+        segments: segments.to_vec(),
+    }
+}
+
+fn line(point: &str) -> Segment {
+    Segment::Line(point.to_string())
+}
+
+#[test]
+fn labels_split_subscripts_and_primes() {
+    let expected = |base: &str, sub: &str, primes: &str| (base.into(), sub.into(), primes.into());
+    assert_eq!(label("A_2'"), expected("A", "2", "′"));
+    assert_eq!(label("B″"), expected("B", "", "″"));
+    assert_eq!(label("P1"), expected("P1", "", ""));
+}
+
+#[test]
+fn closed_paths_are_closed() {
+    let pos = solution(&[("A", 0., 0.), ("B", 1., 0.), ("C", 0., 2.)]);
+    let open = path("A", &[line("B"), line("C")]);
+    assert_eq!(path_data(&open, &pos), "M 0 0 L 1 0 L 0 2");
+    let closed = path("A", &[line("B"), line("C"), line("A")]);
+    assert_eq!(path_data(&closed, &pos), "M 0 0 L 1 0 L 0 2 L 0 0 Z");
+}
+
+#[test]
+fn curves_are_drawn_through_their_controls() {
+    let pos = solution(&[("A", 0., 0.), ("B", 1., 0.), ("C", 0., 2.), ("D", 3., 3.)]);
+    let quadratic = path("A", &[Segment::Quadratic("B".into(), "C".into())]);
+    assert_eq!(path_data(&quadratic, &pos), "M 0 0 Q 1 0 0 2");
+    let cubic = path("A", &[Segment::Cubic("B".into(), "C".into(), "D".into())]);
+    assert_eq!(path_data(&cubic, &pos), "M 0 0 C 1 0 0 2 3 3");
+}
+
+#[test]
+fn construction_points_can_be_hidden() {
+    let pos = solution(&[("A", 0., 0.), ("B", 1., 0.), ("M", 0.5, 5.)]);
+    let paths = [path("A", &[line("B")])];
+    let shown = svg(&pos, &paths, &SvgOptions::default());
+    assert_eq!(shown.matches("<circle").count(), 3);
+    assert!(shown.contains(">M</text>"));
+    let options = SvgOptions {
+        construction: false,
+        ..Default::default()
+    };
+    let hidden = svg(&pos, &paths, &options);
+    assert_eq!(hidden.matches("<circle").count(), 2);
+    assert!(!hidden.contains(">M</text>"));
+    // The box only fits the points shown.
+    assert!(hidden.contains("viewBox=\"-3 -3 7 6\""), "{hidden}");
+}
+
+#[test]
+fn margin_and_stroke_width_are_applied() {
+    let pos = solution(&[("A", 0., 0.), ("B", 4., 2.)]);
+    let paths = [path("A", &[line("B")])];
+    let options = SvgOptions {
+        stroke_width: Some(0.25),
+        margin: Some(1.),
+        ..Default::default()
+    };
+    let drawn = svg(&pos, &paths, &options);
+    assert!(drawn.contains("viewBox=\"-1 -1 6 4\""), "{drawn}");
+    assert!(drawn.contains("width=\"6\" height=\"4\""), "{drawn}");
+    assert!(
+        drawn.contains("stroke=\"black\" stroke-width=\"0.25\""),
+        "{drawn}"
+    );
+}
+
+#[test]
+fn names_are_escaped() {
+    let pos = solution(&[("A<&>_1<", 0., 0.)]);
+    let options = SvgOptions {
+        points: false,
+        axes: false,
+        ..Default::default()
+    };
+    let drawn = svg(&pos, &[], &options);
+    assert!(drawn.contains(">A&lt;&amp;&gt;<tspan"), "{drawn}");
+    assert!(drawn.contains(">1&lt;</tspan>"), "{drawn}");
+    assert!(!drawn.contains("A<&>"));
+}
@@ -0,0 +1,13 @@
+//! Geometry from constraints: parse a document of statements, build a
+//! [`Figure`](parse::Figure) from them, solve it and export the result.
+//!
+//! ```ignore
+//! let (statements, diagnostics) = gcad::parse::parse(document);
+//! let figure = gcad::parse::Figure::from_statements(statements)?;
+//! let mut pos = figure.solve()?;
+//! figure.place(&mut pos);
+//! ```
+
+pub mod export;
+pub mod parse;
+pub mod solver;
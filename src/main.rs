@@ -4,10 +4,11 @@ use std::{
 };
 
 use dioxus::prelude::*;
-use gsolve::math::Vector;
-use parse::Figure;
-
-mod parse;
+use gcad::{
+    export::{bounding_box, label, path_data},
+    parse::{self, Branch, Diagnostic, Figure, Freedom, Path},
+    solver::Vector,
+};
 
 fn main() {
     dioxus::launch(App);
@@ -28,11 +29,51 @@ fn App() -> Element {
 fn GCADDoc() -> Element {
     let mut doc = use_signal(String::default);
     let mut hash = use_hook(|| 0u64);
-    let mut solution: Signal<HashMap<String, gsolve::math::Vector, RandomState>> =
+    let mut solution: Signal<HashMap<String, Vector, RandomState>> =
         use_signal(HashMap::default);
-    let mut err: Signal<Option<(String, String)>> = use_signal(|| None);
+    let mut paths: Signal<Vec<Path>> = use_signal(Vec::new);
+    let mut err: Signal<String> = use_signal(String::new);
+    let mut freedom: Signal<HashMap<String, Freedom>> = use_signal(HashMap::new);
+    let mut branches: Signal<Vec<Branch>> = use_signal(Vec::new);
     // let mut debug_str = use_signal(String::new);
 
+    let mut update = move |text: String| {
+        doc.set(text);
+
+        let (statements, mut diagnostics) = parse::parse(doc.read().as_ref());
+        freedom.set(HashMap::from_iter(Figure::freedom(&statements)));
+        diagnostics.extend('parse: {
+            // debug_str.set(format!("{statements:?}"));
+            let new_hash = {
+                let mut hasher = DefaultHasher::new();
+                statements.hash(&mut hasher);
+                hasher.finish()
+            };
+            if hash == new_hash {
+                break 'parse Vec::new();
+            }
+            hash = new_hash;
+            let fig = match Figure::from_statements(statements) {
+                Err(errors) => break 'parse errors.into_iter().map(Diagnostic::from).collect(),
+                Ok(f) => f,
+            };
+            let mut pos = match fig.solve() {
+                Err(e) => break 'parse vec![e],
+                Ok(f) => f,
+            };
+            let mut checks = fig.unsatisfied(&pos);
+            checks.extend(fig.conflicts(&pos));
+            fig.place(&mut pos);
+            branches.set(fig.branches());
+            paths.set(fig.paths);
+            solution.set(HashMap::from_iter(
+                fig.point_map.into_iter().map(|(point, i)| (point, pos[i])),
+            ));
+            checks
+        });
+        err.set(error_overlay(&doc.read(), &diagnostics));
+    };
+
     let (min, size) = bounding_box(solution.read().values().copied()).unwrap_or_default();
     let svg_font_size = size.y / 30.;
 
@@ -42,58 +83,33 @@ fn GCADDoc() -> Element {
             textarea {
                 id: "gcad-document",
                 value: "{doc}",
-                oninput: move |event| {
-                    doc.set(event.value());
-    
-                    err.set('parse: {
-                        let statements = match parse::parse(doc.read().as_ref()) {
-                            Err(e) => {
-                                let pos = unsafe { e.1.offset_from(doc.read().as_ptr()) };
-                                break 'parse Some((e.0.to_string(), pos as usize))
-                            },
-                            Ok(s) => s,
-                        };
-                        // debug_str.set(format!("{statements:?}"));
-                        let new_hash = {
-                            let mut hasher = DefaultHasher::new();
-                            statements.hash(&mut hasher);
-                            hasher.finish()
-                        };
-                        if hash == new_hash {
-                            break 'parse None;
-                        }
-                        hash = new_hash;
-                        let fig = match Figure::from_statements(statements) {
-                            Err(e) => break 'parse Some((e, 0)),
-                            Ok(f) => f,
-                        };
-                        let pos = match fig.order.solve() {
-                            Err(e) => break 'parse Some((e.to_string(), 0)),
-                            Ok(f) => f,
-                        };
-                        solution.set(HashMap::from_iter(
-                            fig.point_map.into_iter().map(|(point, i)| (point, pos[i])),
-                        ));
-                        None
-                    }.map(|(e, pos)| {
-                        (e, doc.read()[..pos].chars().map(|c| if c.is_whitespace() {
-                            c
-                        } else {
-                            ' '
-                        }).collect())
-                    }));
-                },
+                oninput: move |event| update(event.value()),
             },
-            if let Some((err, err_spacing)) = err.cloned() {
+            if !err.read().is_empty() {
                 textarea {
                     id: "gcad-error",
-                    value: "\n{err_spacing}^{err}"
+                    value: "{err}"
                 }
             }
         },
         div {
             id: "gcad-display-area",
             // "{debug_str}",
+            if !branches.read().is_empty() {
+                div {
+                    id: "gcad-branches",
+                    for (point, position, count) in branches.read().iter().map(|b| (b.point.clone(), b.chosen + 1, b.count)).collect::<Vec<_>>() {
+                        button {
+                            title: "Next position of {point}",
+                            onclick: move |_| {
+                                let text = with_branch(&doc.read(), &point, position % count + 1);
+                                update(text);
+                            },
+                            "{point} {position}/{count}"
+                        }
+                    }
+                }
+            }
             svg {
                 id: "gcad-display",
                 view_box: "{min.x} {min.y} {size.x} {size.y}",
@@ -119,6 +135,12 @@ fn GCADDoc() -> Element {
                     "circle {{
                         fill: black;
                     }}"
+                    "circle.curve {{
+                        fill: darkorange;
+                    }}"
+                    "circle.free {{
+                        fill: red;
+                    }}"
                     "line {{
                         stroke: gray;
                         stroke-width: {svg_font_size/24.}px;
@@ -142,8 +164,17 @@ fn GCADDoc() -> Element {
                     y2: 0,
                     marker_end: "url(#triangle)",
                 },
-                for (point, pos) in solution.cloned() {
+                for d in paths.read().iter().map(|path| path_data(path, &solution.read())) {
+                    path {
+                        d: "{d}",
+                        fill: "none",
+                        stroke: "black",
+                        stroke_width: svg_font_size/12.,
+                    },
+                },
+                for (pos, class, (base, sub, primes)) in solution.cloned().into_iter().map(|(point, pos)| (pos, freedom_class(freedom.read().get(&point)), label(&point))) {
                     circle {
+                        class,
                         cx: pos.x,
                         cy: pos.y,
                         r: svg_font_size/6.,
@@ -151,7 +182,15 @@ fn GCADDoc() -> Element {
                     text {
                         x: pos.x,
                         y: pos.y - svg_font_size/2.,
-                        "{point}",
+                        "{base}",
+                        if !sub.is_empty() {
+                            tspan {
+                                baseline_shift: "sub",
+                                font_size: "70%",
+                                "{sub}",
+                            }
+                        },
+                        "{primes}",
                     },
                 },
             },
@@ -159,20 +198,71 @@ fn GCADDoc() -> Element {
     }
 }
 
-fn bounding_box(mut pos: impl Iterator<Item = Vector>) -> Option<(Vector, Vector)> {
-    let mut min = pos.next()?;
-    let mut max = min;
-    for p in pos {
-        min.x = min.x.min(p.x);
-        min.y = min.y.min(p.y);
-        max.x = max.x.max(p.x);
-        max.y = max.y.max(p.y);
+/// Places a caret and message below each line with a diagnostic.
+/// Only the first diagnostic of each line is shown.
+fn error_overlay(doc: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut errors: HashMap<usize, (usize, String)> = HashMap::new();
+    for diagnostic in diagnostics {
+        let (line, col) = diagnostic.span.line_col(doc);
+        // Hints without a place of their own follow the message.
+        let mut message = diagnostic.message.clone();
+        for note in diagnostic.notes.iter().filter(|n| n.span.is_none()) {
+            message.push_str(&format!(" ({})", note.message));
+        }
+        errors.entry(line).or_insert((col, message));
+    }
+    if errors.is_empty() {
+        return String::new();
+    }
+    // Overlay lines are one below the document lines.
+    let mut overlay = String::new();
+    for (i, line) in doc.split('\n').enumerate() {
+        overlay.push('\n');
+        let Some((col, message)) = errors.get(&(i + 1)) else {
+            continue;
+        };
+        overlay.extend(
+            line.chars()
+                .take(col - 1)
+                .map(|c| if c.is_whitespace() { c } else { ' ' }),
+        );
+        overlay.push('^');
+        overlay.push_str(message);
+    }
+    overlay
+}
+
+/// Sets the `branch` line choosing the position of a point, adding one
+/// if the document has none.
+fn with_branch(doc: &str, point: &str, position: usize) -> String {
+    let branch = format!("branch {point} = {position}");
+    let mut found = false;
+    let mut lines: Vec<&str> = doc
+        .split('\n')
+        .map(|line| {
+            let mut words = line.split_whitespace();
+            if !found && words.next() == Some("branch") && words.next() == Some(point) {
+                found = true;
+                &branch[..]
+            } else {
+                line
+            }
+        })
+        .collect();
+    if !found {
+        if lines.last() == Some(&"") {
+            lines.pop();
+        }
+        lines.push(&branch);
+    }
+    lines.join("\n")
+}
+
+/// The class colouring a point by how constrained it is.
+fn freedom_class(freedom: Option<&Freedom>) -> &'static str {
+    match freedom {
+        Some(Freedom::Curve) => "curve",
+        Some(Freedom::Free) => "free",
+        Some(Freedom::Determined) | None => "",
     }
-    let size = max - min;
-    let margin = (size.x.max(size.y) * 0.25).max(3.);
-    min.x -= margin;
-    min.y -= margin;
-    max.x += margin;
-    max.y += margin;
-    Some((min, max - min))
 }
@@ -1,47 +1,197 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    f64::consts::{PI, TAU},
+    fmt::Display,
+    rc::Rc,
+};
 
+mod diagnostic;
+mod error;
 mod math;
+mod numeric;
+#[cfg(test)]
+mod tests;
 
+pub use diagnostic::{Diagnostic, Span};
+pub use error::FigureError;
+pub use math::{Math, MathExpr, Op, Operand};
+
+/// A parse error and the input at which it occurred.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ParseErr<'a>(pub(super) ParseErrType<'a>, pub(super) &'a str);
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(super) struct ParseErr(pub(super) ParseErrType, pub(super) *const u8);
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub(super) enum ParseErrType {
+pub(super) enum ParseErrType<'a> {
     Nothing,
-    Wrong,
-    No(&'static str),
+    /// Expected one of these tokens or descriptions.
+    Expected(&'static [&'static str]),
+    /// Expected a closing token for the opened text.
+    Unclosed {
+        close: &'static str,
+        what: &'static str,
+        open: &'a str,
+    },
     Invalid,
     Extra,
 }
-impl Default for ParseErrType {
-    fn default() -> Self {
-        Nothing
+impl ParseErr<'_> {
+    /// Whether nothing was parsed from the input.
+    fn is_nothing(&self, input: &str) -> bool {
+        self.0 == Nothing && self.1.as_ptr() == input.as_ptr()
+    }
+    /// The unexpected text.
+    fn found(&self) -> &str {
+        let input = self.1.trim_start();
+        match self.0 {
+            Extra => input.trim_end(),
+            _ => token(input),
+        }
+    }
+    /// Suggests a fix for common mistakes within the line.
+    fn hint(&self, line: &str) -> Option<String> {
+        let found = self.found();
+        // Angles are not closed (e.g. `<A B C>`).
+        if found == ">" {
+            let end = Span::within(line, found)?.start;
+            let mut angle = &line[line[..end].rfind('<')? + 1..end];
+            let points = parse_points(&mut angle, 3).ok()?;
+            return blank(angle)
+                .then(|| format!("did you mean `{}{}`?", ANGLE.0, points.join(" ")));
+        }
+        match self.0 {
+            Expected(["("]) => Some("points are placed with `A = (x, y)`".to_string()),
+            _ => None,
+        }
+    }
+    /// Locates the error within a line at `offset` in the document.
+    fn diagnostic(&self, line: &str, offset: usize) -> Diagnostic {
+        let span = Span::within(line, self.found()).unwrap_or_default();
+        let diagnostic = Diagnostic::new(self.to_string(), span.offset(offset));
+        match self.hint(line) {
+            Some(hint) => diagnostic.with_note(hint, None),
+            None => diagnostic,
+        }
     }
 }
-use math::{parse_math, parse_vector, MathExpr};
-use ParseErrType::*;
-impl Display for ParseErrType {
+impl Display for ParseErr<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Nothing => write!(f, "Nothing"),
-            No(s) => write!(f, "No {s}"),
-            Invalid => write!(f, "Invalid"),
-            Extra => write!(f, "Extra"),
-            Wrong => write!(f, "Wrong"),
+        let found = match self.found() {
+            "" => "end of line".to_string(),
+            found => format!("`{found}`"),
+        };
+        match self.0 {
+            Nothing => write!(f, "expected statement, found {found}"),
+            Extra => write!(f, "unexpected {found}"),
+            Expected(expected) => {
+                let expected: Vec<_> = expected
+                    .iter()
+                    .map(|e| {
+                        // Descriptions are plain words.
+                        if e.chars().all(|c| c.is_alphabetic() || c == ' ') {
+                            e.to_string()
+                        } else {
+                            format!("`{e}`")
+                        }
+                    })
+                    .collect();
+                let expected = match expected.split_last() {
+                    Some((last, [])) => last.clone(),
+                    Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+                    None => "nothing".to_string(),
+                };
+                write!(f, "expected {expected}, found {found}")
+            }
+            Unclosed { close, what, open } => {
+                let open = open.trim_end();
+                write!(
+                    f,
+                    "expected `{close}` to close {what} `{open}`, found {found}"
+                )
+            }
+            Invalid => write!(f, "invalid {found}"),
         }
     }
 }
+impl Default for ParseErrType<'_> {
+    fn default() -> Self {
+        Nothing
+    }
+}
+use math::{parse_math, parse_vector, unsigned_angle};
+use ParseErrType::*;
 
-use gsolve::{
-    math::{Geo, Vector},
-    order::Quantity,
-    Order, PID,
-};
+use crate::solver::{self, Geo, NoPosition, Number, Quantity, SolverBackend, Vector, PID};
 use multimap::MultiMap;
 
 #[derive(Default)]
 pub struct Figure {
-    pub order: Order,
+    pub solver: Box<dyn SolverBackend>,
     pub point_map: HashMap<String, PID>,
+    pub paths: Vec<Path>,
+    chirality: Vec<Statement>,
+    /// The sign of each chirality group, set by the first position chosen
+    /// from one of its angles unless forced.
+    chiral_signs: Vec<Rc<Cell<Option<Number>>>>,
+    /// The chosen position of each point placed by intersecting shapes.
+    choices: HashMap<String, Rc<Choice>>,
+    clusters: Vec<Cluster>,
+    /// Every constraint and whether it is used to place its target.
+    constraints: Vec<(Statement, bool)>,
+    /// Where to move a point of the solved figure.
+    position: Option<(String, Vector)>,
+    /// Which orientation to turn a line of the solved figure to.
+    orientation: Option<(String, String, Number)>,
+}
+/// The positions a point may take and which one is chosen.
+#[derive(Debug, Default)]
+struct Choice {
+    chosen: Cell<usize>,
+    /// How many positions the point could take when last solved.
+    count: Cell<usize>,
+}
+/// A point with more than one position to choose from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    pub point: String,
+    /// The chosen position, counting from 0.
+    pub chosen: usize,
+    pub count: usize,
+}
+/// How free a point remains after its constraints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Freedom {
+    /// Placed by its constraints from determined points.
+    Determined,
+    /// Constrained, but still free to move along a curve or with the
+    /// points it depends on.
+    Curve,
+    /// Unconstrained.
+    Free,
+}
+/// The part a constraint plays in a solved figure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// Used to place its target.
+    Necessary,
+    /// Not used to place its target but holds anyway.
+    Redundant,
+    /// Not used to place its target and does not hold.
+    Conflicting,
+}
+/// Points which depend on each other, solved together numerically.
+struct Cluster {
+    points: Vec<String>,
+    statements: Vec<Statement>,
+    /// Whether solving the cluster failed since it was last reset.
+    failed: Rc<Cell<bool>>,
+}
+impl Cluster {
+    fn error(&self) -> FigureError {
+        FigureError::Unsolvable {
+            points: self.points.clone(),
+            spans: self.statements.iter().map(|s| s.span).collect(),
+        }
+    }
 }
 impl Figure {
     fn add_recursive(
@@ -63,7 +213,8 @@ impl Figure {
             return;
         };
         // Add point and mark as known.
-        let pid = self.order.add_point(quantities);
+        let quantity = self.branch(target, quantities);
+        let pid = self.solver.add_point(vec![quantity]);
         self.point_map.insert(target.clone(), pid);
         map.remove(target); // Unfortunate drop.
                             // Add all dependents.
@@ -71,14 +222,353 @@ impl Figure {
             self.add_recursive(next, tree, map);
         }
     }
-    pub fn from_statements(statements: Vec<Statement>) -> Result<Self, String> {
+    /// Combines the quantities placing a point into one choosing among the
+    /// positions they allow. Positions are counted counterclockwise around
+    /// the first point used, starting from the next point used. Positions
+    /// where the point's other constraints or its chiral angles do not hold
+    /// are dropped, unless none would be left.
+    fn branch(&mut self, point: &str, quantities: Vec<Quantity>) -> Quantity {
+        let choice = self.choices.entry(point.to_string()).or_default().clone();
+        let mut points: Vec<PID> = quantities
+            .iter()
+            .flat_map(|q| q.points.iter().copied())
+            .collect();
+        let count = points.len();
+        // Only constraints using points placed already can check a position.
+        let extras: Vec<Statement> = self
+            .constraints
+            .iter()
+            .filter(|(s, necessary)| {
+                !necessary
+                    && s.target() == point
+                    && s.dependencies()
+                        .iter()
+                        .all(|p| self.point_map.contains_key(p))
+            })
+            .map(|(s, _)| s.clone())
+            .collect();
+        // Chiral angles through the point with their other points placed,
+        // with the sign of their group and their own sign within it.
+        let mut angles = Vec::new();
+        for (statement, group) in self.chirality.iter().zip(&self.chiral_signs) {
+            let StatementType::Chirality(chiralities) = &statement.s_type else {
+                unreachable!()
+            };
+            for (chirality, angle) in chiralities.iter().zip(statement.points.chunks(3)) {
+                if angle.iter().any(|p| p == point)
+                    && angle
+                        .iter()
+                        .all(|p| p == point || self.point_map.contains_key(p))
+                {
+                    angles.push((group.clone(), chirality.sign(), angle.to_vec()));
+                }
+            }
+        }
+        let mut known: Vec<String> = Vec::new();
+        let angle_points = angles.iter().flat_map(|(.., angle)| angle.iter().cloned());
+        for dependency in extras
+            .iter()
+            .flat_map(|s| s.dependencies())
+            .chain(angle_points)
+            .filter(|p| p != point)
+        {
+            if !known.contains(&dependency) {
+                points.push(self.point_map[&dependency]);
+                known.push(dependency);
+            }
+        }
+        Quantity {
+            func: Box::new(move |pos| {
+                let (pos, extra) = pos.split_at(count);
+                let at: HashMap<&String, Vector> =
+                    known.iter().zip(extra.iter().copied()).collect();
+                let holds = |p: &Vector| {
+                    extras.iter().all(|s| {
+                        s.residuals(|q| at.get(q).copied().unwrap_or(*p))
+                            .iter()
+                            .all(|r| r.abs() < numeric::TOLERANCE)
+                    })
+                };
+                let side = |p: &Vector, sign: Number, angle: &[String]| {
+                    let [a, b, c] = [0, 1, 2].map(|i| at.get(&angle[i]).copied().unwrap_or(*p));
+                    turn(a, b, c) * sign
+                };
+                let chiral = |p: &Vector| {
+                    angles.iter().all(|(group, sign, angle)| {
+                        group.get().is_none_or(|g| side(p, *sign, angle) == g)
+                    })
+                };
+                let mut offset = 0;
+                let sets: Vec<Vec<Geo>> = quantities
+                    .iter()
+                    .map(|q| {
+                        offset += q.points.len();
+                        (q.func)(&pos[offset - q.points.len()..offset])
+                    })
+                    .collect();
+                let mut candidates = solver::intersections(&sets);
+                if candidates.iter().any(holds) {
+                    candidates.retain(holds);
+                }
+                if candidates.iter().any(chiral) {
+                    candidates.retain(chiral);
+                }
+                if let Some(&center) = pos.first() {
+                    let start = pos
+                        .iter()
+                        .find(|p| p.dist(center) > 0.)
+                        .map_or(0., |p| (*p - center).angle());
+                    // Positions just short of a full turn are at the start.
+                    let turn = |p: &Vector| {
+                        let turn = ((*p - center).angle() - start).rem_euclid(TAU);
+                        if TAU - turn < numeric::TOLERANCE {
+                            0.
+                        } else {
+                            turn
+                        }
+                    };
+                    candidates.sort_by(|a, b| turn(a).total_cmp(&turn(b)));
+                }
+                choice.count.set(candidates.len());
+                let chosen = choice.chosen.get().min(candidates.len().saturating_sub(1));
+                let Some(&p) = candidates.get(chosen) else {
+                    return Vec::new();
+                };
+                // The chosen position sets the sign of groups without one.
+                for (group, sign, angle) in &angles {
+                    if group.get().is_none() {
+                        group.set(Some(side(&p, *sign, angle)));
+                    }
+                }
+                vec![Geo::Point(p)]
+            }),
+            points,
+        }
+    }
+    /// The points with more than one position when last solved, in the
+    /// order they are placed.
+    pub fn branches(&self) -> Vec<Branch> {
+        let mut branches: Vec<Branch> = self
+            .choices
+            .iter()
+            .filter(|(_, c)| c.count.get() > 1)
+            .map(|(point, c)| Branch {
+                point: point.clone(),
+                chosen: c.chosen.get().min(c.count.get() - 1),
+                count: c.count.get(),
+            })
+            .collect();
+        branches.sort_by_key(|b| self.point_map.get(&b.point).copied());
+        branches
+    }
+    /// Chooses the position of a point for the next solve, counting from 0.
+    pub fn choose(&self, point: &str, chosen: usize) {
+        if let Some(choice) = self.choices.get(point) {
+            choice.chosen.set(chosen);
+        }
+    }
+    /// Finds unplaced points which depend on each other and otherwise
+    /// only on placed points.
+    fn next_cluster(&self, map: &MultiMap<String, Statement>) -> Option<Cluster> {
+        let mut unplaced: Vec<&Statement> = map.iter_all().flat_map(|(_, s)| s).collect();
+        unplaced.sort_by_key(|s| s.span.start);
+        cycles(&unplaced).into_iter().find_map(|points| {
+            let statements: Vec<Statement> = unplaced
+                .iter()
+                .filter(|s| points.contains(s.target()))
+                .map(|&s| s.clone())
+                .collect();
+            // Every other dependency must be placed, and at least one
+            // must anchor the cluster.
+            let mut anchored = false;
+            for dependency in statements.iter().flat_map(|s| s.dependencies()) {
+                if self.point_map.contains_key(&dependency) {
+                    anchored = true;
+                } else if !points.contains(&dependency) {
+                    return None;
+                }
+            }
+            anchored.then(|| Cluster {
+                points,
+                statements,
+                failed: Rc::new(Cell::new(false)),
+            })
+        })
+    }
+    /// Adds each point of the cluster with a quantity solving the whole
+    /// cluster, then adds the points depending on them.
+    fn add_cluster(
+        &mut self,
+        cluster: Cluster,
+        tree: &MultiMap<String, String>,
+        map: &mut MultiMap<String, Statement>,
+    ) {
+        let mut known: Vec<String> = Vec::new();
+        for dependency in cluster.statements.iter().flat_map(|s| s.dependencies()) {
+            if !cluster.points.contains(&dependency) && !known.contains(&dependency) {
+                known.push(dependency);
+            }
+        }
+        let pids: Vec<PID> = known.iter().map(|p| self.point_map[p]).collect();
+        let points = Rc::new(cluster.points.clone());
+        let statements = Rc::new(cluster.statements.clone());
+        let known = Rc::new(known);
+        // The positions of the known points last solved from and the
+        // solution, shared so the cluster is solved once for all its points.
+        let cache = Rc::new(RefCell::new(None));
+        for (i, point) in cluster.points.iter().enumerate() {
+            let (points, statements, known) = (points.clone(), statements.clone(), known.clone());
+            let (failed, cache) = (cluster.failed.clone(), cache.clone());
+            let quantity = Quantity {
+                func: Box::new(move |pos| {
+                    let mut cache = cache.borrow_mut();
+                    if !cache.as_ref().is_some_and(|(solved, _)| solved == pos) {
+                        let known = known.iter().zip(pos.iter().copied()).collect();
+                        let solution = solve_cluster(&points, &statements, &known);
+                        *cache = Some((pos.to_vec(), solution));
+                    }
+                    match cache.as_ref().and_then(|(_, solution)| solution.as_ref()) {
+                        Some(solution) => vec![Geo::Point(solution[i])],
+                        None => {
+                            failed.set(true);
+                            Vec::new()
+                        }
+                    }
+                }),
+                points: pids.clone(),
+            };
+            let pid = self.solver.add_point(vec![quantity]);
+            self.point_map.insert(point.clone(), pid);
+            map.remove(point);
+        }
+        for point in &cluster.points {
+            for next in tree.get_vec(point).map(|v| v.iter()).unwrap_or_default() {
+                self.add_recursive(next, tree, map);
+            }
+        }
+        self.clusters.push(cluster);
+    }
+    /// Builds the figure on the default solver backend. Every cycle is
+    /// reported at once; other errors are reported alone.
+    pub fn from_statements(statements: Vec<Statement>) -> Result<Self, Vec<FigureError>> {
+        Self::with_solver(statements, Box::default())
+    }
+    /// Builds the figure on a solver backend other than the default one.
+    pub fn with_solver(
+        mut statements: Vec<Statement>,
+        solver: Box<dyn SolverBackend>,
+    ) -> Result<Self, Vec<FigureError>> {
+        let mut fig = Figure {
+            solver,
+            ..Default::default()
+        };
+        // Fixing statements only place points; they are not the user's to
+        // verify.
+        let fixing = fixing(&statements);
+        let synthetic = fixing.len();
+        statements.splice(0..0, fixing);
+        let (necessary, _) = orient(&mut statements);
+        let mut paths = Vec::new();
         let mut map = MultiMap::new();
         let mut roots = Vec::new();
         let mut tree = MultiMap::new();
 
+        // Every point must be the target of some statement.
+        let targets: HashSet<&String> = statements
+            .iter()
+            .filter(|s| s.quantity_count() > 0)
+            .map(|s| s.target())
+            .collect();
+        let used = |s: &Statement| match s.s_type {
+            StatementType::Position(_)
+            | StatementType::Orientation(_)
+            | StatementType::Branch(_)
+            | StatementType::Chirality(_)
+            | StatementType::Path(_) => s.points.clone(),
+            StatementType::Comment(_) => Vec::new(),
+            _ => s.dependencies(),
+        };
+        if let Some(point) = statements
+            .iter()
+            .flat_map(used)
+            .find(|p| !targets.contains(p))
+        {
+            let spans = statements
+                .iter()
+                .filter(|s| used(s).contains(&point))
+                .map(|s| s.span)
+                .collect();
+            return Err(vec![FigureError::UnknownPoint { point, spans }]);
+        }
+
+        // Every point needs two constraints.
+        let mut counts: Vec<(&String, usize, Vec<Span>)> = Vec::new();
+        for statement in &statements {
+            let count = statement.quantity_count();
+            if count == 0 {
+                continue;
+            }
+            match counts.iter_mut().find(|(p, ..)| *p == statement.target()) {
+                Some((_, c, spans)) => {
+                    *c += count;
+                    spans.push(statement.span);
+                }
+                None => counts.push((statement.target(), count, vec![statement.span])),
+            }
+        }
+        for (point, count, spans) in counts {
+            let point = point.clone();
+            if count < 2 {
+                return Err(vec![FigureError::TooFewConstraints {
+                    point,
+                    count,
+                    spans,
+                }]);
+            }
+        }
+
         // Set up roots and mapping.
         // For each statement...
-        for statement in statements {
+        for (i, (statement, necessary)) in statements.into_iter().zip(necessary).enumerate() {
+            match statement.s_type {
+                // Chirality only selects between solutions.
+                StatementType::Chirality(_) => {
+                    fig.chirality.push(statement);
+                    fig.chiral_signs.push(Rc::default());
+                    continue;
+                }
+                // Paths are only drawn.
+                StatementType::Path(_) => {
+                    paths.push(statement);
+                    continue;
+                }
+                // Directives only move the solved figure.
+                StatementType::Position(v) => {
+                    fig.position = Some((statement.points[0].clone(), v));
+                    continue;
+                }
+                StatementType::Orientation(m) => {
+                    let [p0, p1] = &statement.points[..] else {
+                        unreachable!()
+                    };
+                    let angle = m.func().map_or(0., |f| f(&[]));
+                    fig.orientation = Some((p0.clone(), p1.clone(), angle));
+                    continue;
+                }
+                StatementType::Branch(n) => {
+                    let choice = fig.choices.entry(statement.points[0].clone()).or_default();
+                    choice.chosen.set(n - 1);
+                    continue;
+                }
+                StatementType::Comment(_) => continue,
+                _ => {}
+            }
+            if i >= synthetic {
+                fig.constraints.push((statement.clone(), necessary));
+            }
+            if !necessary {
+                continue;
+            }
             // Origins are roots.
             if let StatementType::Origin(_) = statement.s_type {
                 roots.push(statement.target().clone());
@@ -91,51 +581,797 @@ impl Figure {
             map.insert(statement.target().clone(), statement);
         }
 
-        let mut fig = Figure::default();
         // Starting from each root...
         for root in roots {
             // Add points when their depencencies are satisfied.
             fig.add_recursive(&root, &tree, &mut map)
         }
 
-        if !map.is_empty() {
-            return Err(format!("Unused {:?} {:?}", fig.point_map, map));
+        // Solve points depending on each other together once everything
+        // else they depend on is placed.
+        while let Some(cluster) = fig.next_cluster(&map) {
+            fig.add_cluster(cluster, &tree, &mut map);
         }
 
+        // Report the points left unplaced, grouped with the points their
+        // statements connect them to.
+        let mut unplaced: Vec<Statement> = map.into_iter().flat_map(|(_, s)| s).collect();
+        unplaced.sort_by_key(|s| s.span.start);
+        // Points depending only on each other have nothing to be placed from.
+        let cycles: Vec<FigureError> = cycles(&unplaced.iter().collect::<Vec<_>>())
+            .into_iter()
+            .filter_map(|points| {
+                let statements: Vec<&Statement> = unplaced
+                    .iter()
+                    .filter(|s| points.contains(s.target()))
+                    .collect();
+                let spans = statements
+                    .iter()
+                    .filter(|s| !s.dependencies().is_empty())
+                    .map(|s| s.span)
+                    .collect();
+                statements
+                    .iter()
+                    .flat_map(|s| s.dependencies())
+                    .all(|p| points.contains(&p))
+                    .then_some(FigureError::Cycle { points, spans })
+            })
+            .collect();
+        if !cycles.is_empty() {
+            return Err(cycles);
+        }
+        if let Some(first) = unplaced.first() {
+            let mut points = vec![first.target().clone()];
+            let mut spans = Vec::new();
+            let mut reached = false;
+            let mut grouped = vec![false; unplaced.len()];
+            // Add statements sharing a point until the group stops growing.
+            let mut grown = true;
+            while grown {
+                grown = false;
+                for (statement, grouped) in unplaced.iter().zip(&mut grouped) {
+                    let mut statement_points = statement.dependencies();
+                    statement_points.push(statement.target().clone());
+                    if *grouped || !statement_points.iter().any(|p| points.contains(p)) {
+                        continue;
+                    }
+                    *grouped = true;
+                    grown = true;
+                    spans.push(statement.span);
+                    for p in statement_points {
+                        if fig.point_map.contains_key(&p) {
+                            reached = true;
+                        } else if !points.contains(&p) {
+                            points.push(p);
+                        }
+                    }
+                }
+            }
+            spans.sort_by_key(|s| s.start);
+            return Err(vec![if reached {
+                FigureError::Unreachable { points, spans }
+            } else {
+                FigureError::NoOrigin { points, spans }
+            }]);
+        }
+        fig.paths = paths
+            .into_iter()
+            .filter_map(|statement| match statement.s_type {
+                StatementType::Path(path) => Some(path),
+                _ => None,
+            })
+            .collect();
+
         Ok(fig)
     }
+    /// Finds how free each point is, in order of first use.
+    pub fn freedom(statements: &[Statement]) -> Vec<(String, Freedom)> {
+        let mut statements: Vec<Statement> = fixing(statements)
+            .into_iter()
+            .chain(statements.iter().cloned())
+            .collect();
+        orient(&mut statements);
+        let mut points: Vec<String> = Vec::new();
+        for statement in &statements {
+            for point in statement
+                .points
+                .iter()
+                .cloned()
+                .chain(statement.dependencies())
+            {
+                if !points.contains(&point) {
+                    points.push(point);
+                }
+            }
+        }
+        let index = |p: &String| points.iter().position(|q| q == p).unwrap();
+        let mut counts = vec![0; points.len()];
+        let mut edges = vec![Vec::new(); points.len()];
+        for statement in &statements {
+            if statement.quantity_count() == 0 {
+                continue;
+            }
+            let i = index(statement.target());
+            counts[i] += statement.quantity_count();
+            edges[i].extend(statement.dependencies().iter().map(index));
+        }
+        // Components come after the components they depend on.
+        let mut determined = vec![false; points.len()];
+        for component in strongly_connected(&edges) {
+            let constrained = component.iter().all(|&i| counts[i] >= 2);
+            let mut anchored = component.len() == 1 && !edges[component[0]].contains(&component[0]);
+            let mut placeable = true;
+            for &j in component.iter().flat_map(|&i| &edges[i]) {
+                if determined[j] {
+                    anchored = true;
+                } else if !component.contains(&j) {
+                    placeable = false;
+                }
+            }
+            if constrained && anchored && placeable {
+                for &i in &component {
+                    determined[i] = true;
+                }
+            }
+        }
+        points
+            .into_iter()
+            .zip(counts.into_iter().zip(determined))
+            .map(|(point, (count, determined))| {
+                let freedom = match (determined, count) {
+                    (true, _) => Freedom::Determined,
+                    (false, 0) => Freedom::Free,
+                    (false, _) => Freedom::Curve,
+                };
+                (point, freedom)
+            })
+            .collect()
+    }
+    /// Moves the solved figure as its position and orientation directives
+    /// ask, keeping its shape.
+    pub fn place(&self, pos: &mut [Vector]) {
+        if let Some((p0, p1, angle)) = &self.orientation {
+            let turn = angle - (pos[self.point_map[p1]] - pos[self.point_map[p0]]).angle();
+            let (sin, cos) = turn.sin_cos();
+            for p in pos.iter_mut() {
+                *p = Vector {
+                    x: cos * p.x - sin * p.y,
+                    y: sin * p.x + cos * p.y,
+                };
+            }
+        }
+        if let Some((p, v)) = &self.position {
+            let p = pos[self.point_map[p]];
+            let (dx, dy) = (v.x - p.x, v.y - p.y);
+            for p in pos.iter_mut() {
+                *p = Vector {
+                    x: p.x + dx,
+                    y: p.y + dy,
+                };
+            }
+        }
+    }
+    /// Classifies every constraint by whether it placed its target and
+    /// otherwise whether it holds for the solved positions.
+    pub fn constraints(&self, pos: &[Vector]) -> Vec<(Span, Constraint)> {
+        self.constraints
+            .iter()
+            .zip(self.verify(pos))
+            .map(|((_, necessary), (span, residual))| {
+                let constraint = if *necessary {
+                    Constraint::Necessary
+                } else if residual < numeric::TOLERANCE {
+                    Constraint::Redundant
+                } else {
+                    Constraint::Conflicting
+                };
+                (span, constraint)
+            })
+            .collect()
+    }
+    /// Measures how far each constraint is from holding for the solved
+    /// positions, before [Figure::place] moves them.
+    pub fn verify(&self, pos: &[Vector]) -> Vec<(Span, Number)> {
+        self.constraints
+            .iter()
+            .map(|(statement, _)| {
+                let residual = statement
+                    .residuals(|p| pos[self.point_map[p]])
+                    .iter()
+                    .fold(0., |max: Number, r| max.max(r.abs()));
+                (statement.span, residual)
+            })
+            .collect()
+    }
+    /// Reports each constraint used to place a point which still does not
+    /// hold (e.g. when solving a cluster stopped short).
+    pub fn unsatisfied(&self, pos: &[Vector]) -> Vec<Diagnostic> {
+        self.constraints
+            .iter()
+            .zip(self.verify(pos))
+            .filter(|((_, necessary), (_, residual))| {
+                *necessary && (*residual >= numeric::TOLERANCE || residual.is_nan())
+            })
+            .map(|((statement, _), (span, residual))| {
+                Diagnostic::new(
+                    format!("`{statement}` does not hold (off by {residual:.2e})"),
+                    span,
+                )
+            })
+            .collect()
+    }
+    /// Reports each conflicting constraint along with the constraints
+    /// which placed its target.
+    pub fn conflicts(&self, pos: &[Vector]) -> Vec<Diagnostic> {
+        self.constraints
+            .iter()
+            .zip(self.constraints(pos))
+            .filter(|(_, (_, constraint))| *constraint == Constraint::Conflicting)
+            .map(|((statement, _), _)| {
+                let target = statement.target();
+                let message = format!("`{statement}` conflicts with the placement of `{target}`");
+                self.constraints
+                    .iter()
+                    .filter(|(s, necessary)| *necessary && s.target() == target)
+                    .fold(Diagnostic::new(message, statement.span), |d, (s, _)| {
+                        d.with_note(format!("`{target}` is placed by `{s}`"), Some(s.span))
+                    })
+            })
+            .collect()
+    }
+    /// Reports the point the backend could not place along with the
+    /// constraints which placed it.
+    fn no_position(&self, NoPosition(pid): NoPosition) -> Diagnostic {
+        let Some(point) = self
+            .point_map
+            .iter()
+            .find(|(_, &p)| p == pid)
+            .map(|(p, _)| p)
+        else {
+            return Diagnostic::new(NoPosition(pid).to_string(), Span::default());
+        };
+        let placing: Vec<&Statement> = self
+            .constraints
+            .iter()
+            .filter(|(s, necessary)| *necessary && s.target() == point)
+            .map(|(s, _)| s)
+            .collect();
+        let span = placing.first().map_or(Span::default(), |s| s.span);
+        placing.iter().fold(
+            Diagnostic::new(format!("no position satisfies `{point}`"), span),
+            |d, s| d.with_note(format!("`{point}` is placed by `{s}`"), Some(s.span)),
+        )
+    }
+    /// Solves the figure, choosing the solution which satisfies every
+    /// chirality statement.
+    pub fn solve(&self) -> Result<Vec<Vector>, Diagnostic> {
+        let mut err = None;
+        let mut attempt = |force: Option<(usize, Number)>| {
+            for (group, sign) in self.chiral_signs.iter().enumerate() {
+                sign.set(force.and_then(|(g, s)| (g == group).then_some(s)));
+            }
+            for cluster in &self.clusters {
+                cluster.failed.set(false);
+            }
+            match self.solver.solve() {
+                Ok(pos) => self
+                    .chirality
+                    .iter()
+                    .all(|s| s.is_chiral(&self.point_map, &pos))
+                    .then_some(pos),
+                Err(e) => {
+                    err.get_or_insert(match self.clusters.iter().find(|c| c.failed.get()) {
+                        Some(cluster) => cluster.error().into(),
+                        None => self.no_position(e),
+                    });
+                    None
+                }
+            }
+        };
+        // Each chirality group takes the sign of the first position chosen
+        // from its angles.
+        if let Some(pos) = attempt(None) {
+            return Ok(pos);
+        }
+        // Otherwise try each group the other way around, once.
+        let flipped: Vec<(usize, Number)> = self
+            .chiral_signs
+            .iter()
+            .enumerate()
+            .filter_map(|(group, sign)| Some((group, -sign.get()?)))
+            .collect();
+        for force in flipped {
+            if let Some(pos) = attempt(Some(force)) {
+                return Ok(pos);
+            }
+        }
+        Err(err.unwrap_or_else(|| {
+            let statements: Vec<_> = self.chirality.iter().map(|s| s.to_string()).collect();
+            let message = format!("no solution satisfies {}", statements.join(" and "));
+            self.chirality[1..]
+                .iter()
+                .fold(Diagnostic::new(message, self.chirality[0].span), |d, s| {
+                    d.with_note("chirality also stated here", Some(s.span))
+                })
+        }))
+    }
+}
+
+/// Chooses the point each statement places: the last of its points to be
+/// placed, starting from the origins. Returns whether each statement is
+/// needed to place its target, as any others only check the solution, and
+/// how many points are placed one at a time.
+///
+/// Each point is placed by its origin or else its first two constraints
+/// from points placed before it. Points left unplaced keep the last point
+/// of their statements where they can, to be solved together.
+fn orient(statements: &mut [Statement]) -> (Vec<bool>, usize) {
+    let mut necessary = vec![false; statements.len()];
+    let mut oriented = vec![false; statements.len()];
+    let mut placed: HashSet<String> = HashSet::new();
+    let mut points: Vec<String> = Vec::new();
+    for (i, statement) in statements.iter().enumerate() {
+        if statement.quantity_count() == 0 {
+            continue;
+        }
+        if let StatementType::Origin(_) = statement.s_type {
+            oriented[i] = true;
+            necessary[i] = placed.insert(statement.target().clone());
+        }
+        for point in statement
+            .points
+            .iter()
+            .chain(statement.dependencies().iter())
+        {
+            if !points.contains(point) {
+                points.push(point.clone());
+            }
+        }
+    }
+    // Place points until none can be placed from the points before them.
+    let mut progress = true;
+    while progress {
+        progress = false;
+        for point in &points {
+            if placed.contains(point) {
+                continue;
+            }
+            let placing: Vec<(usize, usize)> = statements
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| !oriented[i])
+                .filter_map(|(i, s)| {
+                    let j = s.points.iter().position(|p| p == point)?;
+                    let placeable = s.can_place(j)
+                        && s.dependencies_placing(j).iter().all(|p| placed.contains(p));
+                    placeable.then_some((i, j))
+                })
+                .collect();
+            let count: usize = placing
+                .iter()
+                .map(|&(i, _)| statements[i].quantity_count())
+                .sum();
+            if count < 2 {
+                continue;
+            }
+            let mut count = 0;
+            for (i, j) in placing {
+                statements[i].target = j;
+                oriented[i] = true;
+                necessary[i] = count < 2;
+                count += statements[i].quantity_count();
+            }
+            placed.insert(point.clone());
+            progress = true;
+        }
+    }
+    // The rest place their last unplaced point, by their first two
+    // constraints.
+    for i in 0..statements.len() {
+        let statement = &mut statements[i];
+        if oriented[i] || statement.quantity_count() == 0 {
+            continue;
+        }
+        if let Some(j) = (0..statement.points.len())
+            .rev()
+            .find(|&j| statement.can_place(j) && !placed.contains(&statement.points[j]))
+        {
+            statement.target = j;
+        }
+        let target = statements[i].target();
+        necessary[i] = !placed.contains(target)
+            && statements[..i]
+                .iter()
+                .zip(&oriented)
+                .filter(|(s, &o)| !o && s.quantity_count() > 0 && s.target() == target)
+                .count()
+                < 2;
+    }
+    (necessary, placed.len())
+}
+
+/// Which way the angle from `a` around `b` to `c` turns: `1` for
+/// counterclockwise and `-1` for clockwise.
+fn turn(a: Vector, b: Vector, c: Vector) -> Number {
+    let (a, c) = (a - b, c - b);
+    (a.x * c.y - a.y * c.x).signum()
+}
+
+/// Statements fixing a figure without an origin in place: a point at the
+/// origin and, unless its constraints already turn the figure, the first
+/// point placed from it by a single constraint to its right. The first
+/// point from which the most points can be placed is chosen, so every
+/// constraint still places a point.
+fn fixing(statements: &[Statement]) -> Vec<Statement> {
+    let constraints: Vec<&Statement> = statements
+        .iter()
+        .filter(|s| s.quantity_count() > 0)
+        .collect();
+    if constraints
+        .iter()
+        .any(|s| matches!(s.s_type, StatementType::Origin(_)))
+    {
+        return Vec::new();
+    }
+    let mut points: Vec<&String> = Vec::new();
+    for point in constraints.iter().flat_map(|s| s.points.iter()) {
+        if !points.contains(&point) {
+            points.push(point);
+        }
+    }
+    let mut best: Option<(usize, Vec<Statement>)> = None;
+    for origin in points.iter().copied() {
+        let fixing = fixing_from(origin, &constraints);
+        let mut fixed: Vec<Statement> = fixing.iter().chain(statements).cloned().collect();
+        let (_, placed) = orient(&mut fixed);
+        if best.as_ref().is_none_or(|(most, _)| placed > *most) {
+            best = Some((placed, fixing));
+        }
+        if placed == points.len() {
+            break;
+        }
+    }
+    best.map_or(Vec::new(), |(_, fixing)| fixing)
+}
+
+/// Statements placing `origin` at the origin and the first point placed
+/// from it by a single constraint to its right.
+fn fixing_from(origin: &String, constraints: &[&Statement]) -> Vec<Statement> {
+    let span = constraints
+        .iter()
+        .find(|s| s.points.contains(origin))
+        .map_or(Span::default(), |s| s.span);
+    let mut fixing = vec![Statement {
+        span,
+        ..Statement::new(StatementType::Origin(Vector::ZERO), vec![origin.clone()])
+    }];
+    // Constraints placing each point from the origin alone.
+    let mut placing: Vec<(&String, usize, Span)> = Vec::new();
+    for statement in constraints {
+        for (i, point) in statement.points.iter().enumerate() {
+            if point == origin
+                || !statement.can_place(i)
+                || statement.dependencies_placing(i) != [origin.clone()]
+            {
+                continue;
+            }
+            match placing.iter_mut().find(|(p, ..)| *p == point) {
+                Some((_, count, _)) => *count += statement.quantity_count(),
+                None => placing.push((point, statement.quantity_count(), statement.span)),
+            }
+        }
+    }
+    if placing.iter().any(|(_, count, _)| *count >= 2) {
+        return fixing;
+    }
+    if let Some((point, _, span)) = placing.first() {
+        let zero = parse_math("0").expect("zero is a number");
+        fixing.push(Statement {
+            span: *span,
+            ..Statement::new(
+                StatementType::Quantity(QuantityType::Orientation, zero),
+                vec![origin.clone(), (*point).clone()],
+            )
+        });
+    }
+    fixing
 }
 
-pub fn parse(document: &str) -> Result<Vec<Statement>, ParseErr> {
+/// Places points which depend on each other so that all of their
+/// statements hold, given the positions of the other points they use.
+fn solve_cluster(
+    points: &[String],
+    statements: &[Statement],
+    known: &HashMap<&String, Vector>,
+) -> Option<Vec<Vector>> {
+    // Start spread around the known points.
+    let n = known.len() as Number;
+    let center = known.values().fold(Vector::ZERO, |c, v| Vector {
+        x: c.x + v.x / n,
+        y: c.y + v.y / n,
+    });
+    let radius = known.values().map(|v| v.dist(center)).fold(1., Number::max);
+    let solve = |turn: Number| {
+        let start = (0..points.len())
+            .flat_map(|i| {
+                let v = Vector::from_angle(turn + 2.4 * i as Number);
+                [center.x + radius * v.x, center.y + radius * v.y]
+            })
+            .collect();
+        let solution = numeric::least_squares(start, |x| {
+            let pos = |p: &String| match points.iter().position(|q| q == p) {
+                Some(i) => Vector {
+                    x: x[2 * i],
+                    y: x[2 * i + 1],
+                },
+                None => known[p],
+            };
+            statements.iter().flat_map(|s| s.residuals(pos)).collect()
+        })?;
+        Some(
+            solution
+                .chunks(2)
+                .map(|v| Vector { x: v[0], y: v[1] })
+                .collect::<Vec<_>>(),
+        )
+    };
+    // Prefer solutions where no two points coincide.
+    let distinct = |solution: &Vec<Vector>| {
+        solution.iter().enumerate().all(|(i, a)| {
+            known
+                .values()
+                .chain(&solution[..i])
+                .all(|b| a.dist(*b) > numeric::TOLERANCE.sqrt())
+        })
+    };
+    let mut fallback = None;
+    for attempt in 0..8 {
+        let Some(solution) = solve(1. + 0.8 * attempt as Number) else {
+            continue;
+        };
+        if distinct(&solution) {
+            return Some(solution);
+        }
+        fallback.get_or_insert(solution);
+    }
+    fallback
+}
+
+/// Finds the targets of the statements which depend on each other, each
+/// cycle in the order its points are first placed by a statement.
+fn cycles(statements: &[&Statement]) -> Vec<Vec<String>> {
+    let mut points: Vec<&String> = Vec::new();
+    for statement in statements {
+        if !points.contains(&statement.target()) {
+            points.push(statement.target());
+        }
+    }
+    let mut edges = vec![Vec::new(); points.len()];
+    for statement in statements {
+        let i = points
+            .iter()
+            .position(|p| *p == statement.target())
+            .unwrap();
+        for dependency in statement.dependencies() {
+            if let Some(j) = points.iter().position(|p| **p == dependency) {
+                edges[i].push(j);
+            }
+        }
+    }
+    let mut cycles: Vec<Vec<usize>> = strongly_connected(&edges)
+        .into_iter()
+        .filter(|c| c.len() > 1 || edges[c[0]].contains(&c[0]))
+        .collect();
+    for cycle in &mut cycles {
+        cycle.sort();
+    }
+    cycles.sort();
+    cycles
+        .into_iter()
+        .map(|cycle| cycle.iter().map(|&i| points[i].clone()).collect())
+        .collect()
+}
+
+/// Finds the strongly connected components of a graph, where `edges[i]`
+/// are the nodes that node `i` points to (Tarjan's algorithm).
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            let index = self.next;
+            self.next += 1;
+            self.index[v] = Some(index);
+            self.low[v] = index;
+            self.stack.push(v);
+            self.on_stack[v] = true;
+            for &w in &self.edges[v] {
+                match self.index[w] {
+                    None => {
+                        self.visit(w);
+                        self.low[v] = self.low[v].min(self.low[w]);
+                    }
+                    Some(i) if self.on_stack[w] => self.low[v] = self.low[v].min(i),
+                    Some(_) => {}
+                }
+            }
+            // The root of a component pops it off the stack.
+            if self.low[v] == index {
+                let mut component = Vec::new();
+                while let Some(w) = self.stack.pop() {
+                    self.on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+    let mut tarjan = Tarjan {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; edges.len()],
+        next: 0,
+        components: Vec::new(),
+    };
+    for v in 0..edges.len() {
+        if tarjan.index[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+    tarjan.components
+}
+
+/// Parses every line of the document, skipping lines with errors.
+pub fn parse(document: &str) -> (Vec<Statement>, Vec<Diagnostic>) {
     let mut statements = Vec::new();
-    for line in document.lines() {
-        statements.append(&mut parse_line(line)?);
+    let mut diagnostics = Vec::new();
+    let mut offset = 0;
+    for line in document.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        // Statements span their trimmed line.
+        let trimmed = line.trim();
+        let span = Span::within(line, trimmed)
+            .unwrap_or_default()
+            .offset(start);
+        match parse_line(line) {
+            Ok(line_statements) => {
+                for mut statement in line_statements {
+                    statement.span = span;
+                    statements.push(statement);
+                }
+            }
+            Err(e) => diagnostics.push(e.diagnostic(line, start)),
+        }
     }
-    Ok(statements)
+    (statements, diagnostics)
 }
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum StatementType {
     Origin(Vector),
+    /// Moves the solved figure so the point is at the position.
+    Position(Vector),
+    /// Turns the solved figure so the line has the orientation.
+    Orientation(MathExpr),
+    /// Chooses the position of a point, counting from 1.
+    Branch(usize),
     Quantity(QuantityType, MathExpr),
+    Parallel,
+    Perpendicular,
+    Collinear,
+    Chirality(Vec<Chirality>),
+    Path(Path),
+    Comment(String),
+}
+/// Relative sign of an angle in a chirality statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chirality {
+    /// `±∠A B C` or `+/-<A B C`
+    Pro,
+    /// `∓∠A B C` or `-/+<A B C`
+    Anti,
+}
+impl Chirality {
+    fn sign(&self) -> Number {
+        match self {
+            Self::Pro => 1.,
+            Self::Anti => -1.,
+        }
+    }
+}
+/// A drawn path (e.g. `A→B→C`).
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Path {
+    pub start: String,
+    pub segments: Vec<Segment>,
 }
-#[derive(Debug, Clone, Copy, Hash)]
+impl Path {
+    /// Whether the path ends where it begins.
+    pub fn is_closed(&self) -> bool {
+        self.segments
+            .last()
+            .is_some_and(|segment| *segment.end() == self.start)
+    }
+    pub fn points(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.start).chain(self.segments.iter().flat_map(|segment| {
+            let points: Vec<_> = match segment {
+                Segment::Line(p0) => vec![p0],
+                Segment::Quadratic(p0, p1) => vec![p0, p1],
+                Segment::Cubic(p0, p1, p2) => vec![p0, p1, p2],
+            };
+            points
+        }))
+    }
+}
+impl Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.start)?;
+        for segment in &self.segments {
+            let arrow = ARROW.0;
+            match segment {
+                Segment::Line(p0) => write!(f, "{arrow}{p0}"),
+                Segment::Quadratic(p0, p1) => write!(f, "-{p0}{arrow}{p1}"),
+                Segment::Cubic(p0, p1, p2) => write!(f, "-{p0}-{p1}{arrow}{p2}"),
+            }?;
+        }
+        Ok(())
+    }
+}
+/// A path segment from the end of the previous segment.
+/// Control points come before the end point.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum Segment {
+    /// `A→B`
+    Line(String),
+    /// `A-B→C`
+    Quadratic(String, String),
+    /// `A-B-C→D`
+    Cubic(String, String, String),
+}
+impl Segment {
+    pub fn end(&self) -> &String {
+        match self {
+            Segment::Line(p) | Segment::Quadratic(_, p) | Segment::Cubic(_, _, p) => p,
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum QuantityType {
     Distance,
     Orientation,
+    Angle,
 }
 impl QuantityType {
-    fn parser(&self) -> fn(&mut &str) -> Result<Vec<String>, ParseErr> {
+    fn parser(&self) -> for<'a> fn(&mut &'a str) -> Result<Vec<String>, ParseErr<'a>> {
         match self {
             Self::Distance => parse_distance,
             Self::Orientation => parse_orientation,
+            Self::Angle => parse_angle,
         }
     }
 }
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct Statement {
     s_type: StatementType,
     points: Vec<String>,
+    /// The index of the point placed, the last one unless the figure
+    /// places another (see [Statement::can_place]).
+    target: usize,
+    span: Span,
 }
 impl Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -144,33 +1380,160 @@ impl Display for Statement {
                 let p = &self.points[0];
                 write!(f, "{p} = {v}")
             }
+            StatementType::Position(v) => {
+                let p = &self.points[0];
+                write!(f, "position {p} = {v}")
+            }
+            StatementType::Orientation(m) => {
+                let p0 = &self.points[0];
+                let p1 = &self.points[1];
+                write!(f, "orientation {p0} {p1} = {m}")
+            }
+            StatementType::Branch(n) => {
+                let p = &self.points[0];
+                write!(f, "branch {p} = {n}")
+            }
             StatementType::Quantity(t, m) => {
                 let p0 = &self.points[0];
                 let p1 = &self.points[1];
                 match t {
-                    QuantityType::Distance => write!(f, "|{p0} {p1}| = {m:?}"),
-                    QuantityType::Orientation => write!(f, "<{p0} {p1}> = {m:?}"),
+                    QuantityType::Distance => write!(f, "|{p0} {p1}| = {m}"),
+                    QuantityType::Orientation => write!(f, "<{p0} {p1}> = {m}"),
+                    QuantityType::Angle => {
+                        let p2 = &self.points[2];
+                        write!(f, "{}{p0} {p1} {p2} = {m}", ANGLE.0)
+                    }
+                }
+            }
+            StatementType::Parallel => {
+                let [p0, p1, p2, p3] = &self.points[..] else {
+                    unreachable!()
+                };
+                write!(f, "{p0} {p1} {} {p2} {p3}", PARALLEL.0)
+            }
+            StatementType::Perpendicular => {
+                let [p0, p1, p2, p3] = &self.points[..] else {
+                    unreachable!()
+                };
+                write!(f, "{p0} {p1} {} {p2} {p3}", PERPENDICULAR.0)
+            }
+            StatementType::Collinear => {
+                let [p0, p1, p2] = &self.points[..] else {
+                    unreachable!()
+                };
+                write!(f, "{p0}-{p1}-{p2}")
+            }
+            StatementType::Chirality(chiralities) => {
+                for (i, (chirality, angle)) in
+                    chiralities.iter().zip(self.points.chunks(3)).enumerate()
+                {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let sign = match chirality {
+                        Chirality::Pro => PRO.0,
+                        Chirality::Anti => ANTI.0,
+                    };
+                    write!(f, "{sign}{}{}", ANGLE.0, angle.join(" "))?;
                 }
+                Ok(())
             }
+            StatementType::Path(path) => write!(f, "{path}"),
+            StatementType::Comment(text) => write!(f, "\"{text}\""),
         }
     }
 }
 impl Statement {
+    fn new(s_type: StatementType, points: Vec<String>) -> Self {
+        Self {
+            s_type,
+            target: points.len().saturating_sub(1),
+            points,
+            span: Span::default(),
+        }
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn statement_type(&self) -> &StatementType {
+        &self.s_type
+    }
+    /// The points the statement uses.
+    pub fn points(&self) -> &[String] {
+        &self.points
+    }
+    /// The points other than the target, none for statements without
+    /// points (e.g. comments).
     fn dependencies(&self) -> Vec<String> {
-        let mut points: Vec<String> = self.points[..self.points.len() - 1]
-            .iter()
-            .cloned()
-            .collect();
-        match &self.s_type {
-            StatementType::Quantity(t, m) => points.extend(m.points.iter().cloned()),
-            _ => {}
+        self.dependencies_placing(self.target)
+    }
+    /// The points other than the one at `t`, were the statement to place it.
+    fn dependencies_placing(&self, t: usize) -> Vec<String> {
+        let mut points: Vec<String> = self
+            .oriented_to(t)
+            .split_last()
+            .map_or(Vec::new(), |(_, rest)| {
+                rest.iter().map(|&p| p.clone()).collect()
+            });
+        if let StatementType::Quantity(_, m) = &self.s_type {
+            points.extend(m.points.iter().cloned());
         }
         points
     }
+    /// Whether the statement can place its point at `i` from its other
+    /// points. Relations between lines and collinear points place any point
+    /// used once, and angles either end.
+    fn can_place(&self, i: usize) -> bool {
+        let point = &self.points[i];
+        if self.points.iter().filter(|p| *p == point).count() > 1 {
+            return false;
+        }
+        match &self.s_type {
+            StatementType::Quantity(QuantityType::Distance | QuantityType::Orientation, m) => {
+                !m.points.contains(point)
+            }
+            // Not the vertex, which would be on an arc.
+            StatementType::Quantity(QuantityType::Angle, m) => i != 1 && !m.points.contains(point),
+            StatementType::Parallel | StatementType::Perpendicular | StatementType::Collinear => {
+                true
+            }
+            StatementType::Origin(_) => i == 0,
+            StatementType::Position(_)
+            | StatementType::Orientation(_)
+            | StatementType::Branch(_)
+            | StatementType::Chirality(_)
+            | StatementType::Path(_)
+            | StatementType::Comment(_) => false,
+        }
+    }
+    /// The points ordered as if the statement were written with its target
+    /// last (e.g. `C D ∥ A B` to place `B` by `A B ∥ C D`).
+    fn oriented(&self) -> Vec<&String> {
+        self.oriented_to(self.target)
+    }
+    fn oriented_to(&self, t: usize) -> Vec<&String> {
+        let p = &self.points;
+        match self.s_type {
+            StatementType::Quantity(QuantityType::Angle, _) if t == 0 => vec![&p[2], &p[1], &p[0]],
+            StatementType::Parallel | StatementType::Perpendicular => {
+                let (line, other) = if t < 2 { (0, 2) } else { (2, 0) };
+                let partner = if t == line { line + 1 } else { line };
+                vec![&p[other], &p[other + 1], &p[partner], &p[t]]
+            }
+            _ => p
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != t)
+                .map(|(_, p)| p)
+                .chain(p.get(t))
+                .collect(),
+        }
+    }
     fn quantity(&self, point_map: &HashMap<String, PID>) -> Option<Quantity> {
-        let mut points = self.points[..self.points.len() - 1]
+        let oriented = self.oriented();
+        let mut points = oriented[..oriented.len() - 1]
             .iter()
-            .map(|p| point_map.get(p).copied())
+            .map(|&p| point_map.get(p).copied())
             .collect::<Option<Vec<_>>>()?;
         Some(Quantity {
             func: match &self.s_type {
@@ -191,53 +1554,219 @@ impl Statement {
                         QuantityType::Distance => {
                             Box::new(move |pos| vec![Geo::Circle(pos[0], m_func(&pos[1..]))])
                         }
-                        QuantityType::Orientation => Box::new(move |pos| {
-                            vec![Geo::Ray(pos[0], Vector::from_angle(m_func(&pos[1..])))]
+                        // The first point is on the ray back from the second.
+                        QuantityType::Orientation => {
+                            let back = if self.target == 0 { PI } else { 0. };
+                            Box::new(move |pos| {
+                                let angle = m_func(&pos[1..]) + back;
+                                vec![Geo::Ray(pos[0], Vector::from_angle(angle))]
+                            })
+                        }
+                        // Unsigned angles may open to either side of the first ray.
+                        QuantityType::Angle => Box::new(move |pos| {
+                            let base = (pos[0] - pos[1]).angle();
+                            let angle = m_func(&pos[2..]);
+                            vec![
+                                Geo::Ray(pos[1], Vector::from_angle(base + angle)),
+                                Geo::Ray(pos[1], Vector::from_angle(base - angle)),
+                            ]
                         }),
                     }
                 }
+                // The target is on the line through the third point.
+                StatementType::Parallel => {
+                    Box::new(move |pos| vec![Geo::Line(pos[2], pos[1] - pos[0])])
+                }
+                StatementType::Perpendicular => Box::new(move |pos| {
+                    let d = pos[1] - pos[0];
+                    vec![Geo::Line(pos[2], Vector { x: -d.y, y: d.x })]
+                }),
+                StatementType::Collinear => {
+                    Box::new(move |pos| vec![Geo::Line(pos[0], pos[1] - pos[0])])
+                }
+                StatementType::Position(_)
+                | StatementType::Orientation(_)
+                | StatementType::Branch(_)
+                | StatementType::Chirality(_)
+                | StatementType::Path(_)
+                | StatementType::Comment(_) => return None,
             },
             points,
         })
     }
     fn target(&self) -> &String {
-        self.points.last().unwrap()
+        &self.points[self.target]
+    }
+    /// How far the statement is from holding for the given positions,
+    /// one value per constraint it places on its target.
+    fn residuals(&self, pos: impl Fn(&String) -> Vector) -> Vec<Number> {
+        let p: Vec<Vector> = self.points.iter().map(&pos).collect();
+        // Signed distance of the target from a line.
+        let from_line = |o: Vector, d: Vector, t: Vector, normal: bool| {
+            let q = t - o;
+            let len = d.dist(Vector::ZERO);
+            if normal {
+                (d.x * q.x + d.y * q.y) / len
+            } else {
+                (d.x * q.y - d.y * q.x) / len
+            }
+        };
+        match &self.s_type {
+            StatementType::Origin(v) => vec![p[0].x - v.x, p[0].y - v.y],
+            StatementType::Quantity(t, m) => {
+                let Ok(m_func) = m.func() else {
+                    return vec![Number::NAN];
+                };
+                let value = m_func(&m.points.iter().map(&pos).collect::<Vec<_>>());
+                vec![match t {
+                    QuantityType::Distance => p[0].dist(p[1]) - value,
+                    QuantityType::Orientation => {
+                        ((p[1] - p[0]).angle() - value + PI).rem_euclid(TAU) - PI
+                    }
+                    QuantityType::Angle => unsigned_angle(p[0], p[1], p[2]) - value,
+                }]
+            }
+            StatementType::Parallel => vec![from_line(p[2], p[1] - p[0], p[3], false)],
+            StatementType::Perpendicular => vec![from_line(p[2], p[1] - p[0], p[3], true)],
+            StatementType::Collinear => vec![from_line(p[0], p[1] - p[0], p[2], false)],
+            StatementType::Position(_)
+            | StatementType::Orientation(_)
+            | StatementType::Branch(_)
+            | StatementType::Chirality(_)
+            | StatementType::Path(_)
+            | StatementType::Comment(_) => Vec::new(),
+        }
+    }
+    /// How many constraints the statement places on its target.
+    fn quantity_count(&self) -> usize {
+        match self.s_type {
+            StatementType::Origin(_) => 2,
+            StatementType::Quantity(..)
+            | StatementType::Parallel
+            | StatementType::Perpendicular
+            | StatementType::Collinear => 1,
+            StatementType::Position(_)
+            | StatementType::Orientation(_)
+            | StatementType::Branch(_)
+            | StatementType::Chirality(_)
+            | StatementType::Path(_)
+            | StatementType::Comment(_) => 0,
+        }
+    }
+    /// Whether all Pro angles share one sign and all Anti angles have the
+    /// opposite sign.
+    fn is_chiral(&self, point_map: &HashMap<String, PID>, pos: &[Vector]) -> bool {
+        let StatementType::Chirality(chiralities) = &self.s_type else {
+            return true;
+        };
+        let mut signs = chiralities
+            .iter()
+            .zip(self.points.chunks(3))
+            .map(|(chirality, angle)| {
+                let [a, b, c] = [0, 1, 2].map(|i| pos[point_map[&angle[i]]]);
+                turn(a, b, c) * chirality.sign()
+            });
+        let Some(first) = signs.next() else {
+            return true;
+        };
+        signs.all(|s| s == first)
     }
 }
 
-fn parse_line(line: &str) -> Result<Vec<Statement>, ParseErr> {
+fn parse_line(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    let (line, comment) = parse_comment(line)?;
+    let mut statements = parse_statements(line)?;
+    statements.extend(comment);
+    Ok(statements)
+}
+
+/// Splits a trailing comment (e.g. `|A B| = 1 "comment"`) from the line.
+fn parse_comment(line: &str) -> Result<(&str, Option<Statement>), ParseErr<'_>> {
+    let Some((code, rest)) = line.split_once('"') else {
+        return Ok((line, None));
+    };
+    let Some((text, mut rest)) = rest.split_once('"') else {
+        return Err(ParseErr(
+            Unclosed {
+                close: "\"",
+                what: "comment",
+                open: &line[code.len()..],
+            },
+            &line[line.len()..],
+        ));
+    };
+    space(&mut rest);
+    if !rest.is_empty() {
+        return Err(ParseErr(Extra, rest));
+    }
+    Ok((
+        code,
+        Some(Statement::new(
+            StatementType::Comment(text.to_string()),
+            Vec::new(),
+        )),
+    ))
+}
+
+fn parse_statements(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
     if blank(line) {
         return Ok(Vec::new());
     }
-    let mut err = ParseErr(Nothing, line.as_ptr());
-    for parser in [parse_origin, parse_multi_expr] {
+    let mut err = ParseErr(Nothing, line);
+    for parser in [
+        parse_directive,
+        parse_chirality,
+        parse_multi_expr,
+        parse_parallel,
+        parse_perpendicular,
+        parse_collinear,
+        parse_path,
+        parse_origin,
+    ] {
         let e = match (parser)(line) {
             Ok(s) => return Ok(s),
             Err(e) => e,
         };
-        if err.0 == Nothing && err.1 == line.as_ptr() {
+        // Report the error from the parser that got furthest.
+        if !e.is_nothing(line)
+            && (err.is_nothing(line) || position(line, &e) > position(line, &err))
+        {
             err = e;
         }
     }
     Err(err)
 }
 
-fn parse_multi_expr(line: &str) -> Result<Vec<Statement>, ParseErr> {
+/// Where an error occurred within the line.
+fn position(line: &str, err: &ParseErr) -> usize {
+    Span::within(line, err.1).map_or(0, |span| span.start)
+}
+
+fn parse_multi_expr(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
     let exprs: Vec<_> = line.split("=").map(|e| e.trim_start()).collect();
+    // A lone value states nothing.
+    if exprs.len() < 2 {
+        return Err(ParseErr(Nothing, line));
+    }
 
     let mut errs = Vec::new();
-    'parsers: for qt in [QuantityType::Distance, QuantityType::Orientation] {
+    'parsers: for qt in [
+        QuantityType::Distance,
+        QuantityType::Orientation,
+        QuantityType::Angle,
+    ] {
         let mut statements = Vec::new();
         for mut expr in exprs[..exprs.len() - 1].iter().copied() {
             let points = match (qt.parser())(&mut expr) {
                 Ok(c) => c,
                 Err(e) => {
-                    errs.push(e);
+                    // Continue past the `=` so the error finds it.
+                    errs.push(ParseErr(e.0, &line[position(line, &e)..]));
                     continue 'parsers;
                 }
             };
             if !blank(expr) {
-                errs.push(ParseErr(Extra, expr.as_ptr()));
+                errs.push(ParseErr(Extra, expr));
                 continue 'parsers;
             }
             statements.push(points);
@@ -245,13 +1774,14 @@ fn parse_multi_expr(line: &str) -> Result<Vec<Statement>, ParseErr> {
         let n = match if let Some(mut expr) = exprs.last().copied() {
             parse_math(expr).map_err(|err| match err.0 {
                 Nothing => match (qt.parser())(&mut expr) {
-                    Ok(_) => ParseErr(No("value"), expr.as_ptr()),
+                    Ok(_) => ParseErr(Expected(&["value"]), expr),
+                    Err(_) if blank(expr) => ParseErr(Expected(&["value"]), expr),
                     Err(e) => e,
                 },
                 _ => err,
             })
         } else {
-            Err(ParseErr(Nothing, exprs.last().unwrap_or(&line).as_ptr()))
+            Err(ParseErr(Nothing, exprs.last().copied().unwrap_or(line)))
         } {
             Ok(n) => n,
             Err(e) => {
@@ -261,67 +1791,358 @@ fn parse_multi_expr(line: &str) -> Result<Vec<Statement>, ParseErr> {
         };
         return Ok(statements
             .into_iter()
-            .map(|points| Statement {
-                s_type: StatementType::Quantity(qt, n.clone()),
-                points,
-            })
+            .map(|points| Statement::new(StatementType::Quantity(qt, n.clone()), points))
             .collect());
     }
 
     let err = errs
         .into_iter()
         .reduce(|err, e| {
-            if err.0 == Nothing && err.1 == line.as_ptr() {
+            if err.is_nothing(line)
+                || (!e.is_nothing(line) && position(line, &e) > position(line, &err))
+            {
                 e
             } else {
                 err
             }
         })
-        .unwrap_or(ParseErr(Nothing, line.as_ptr()));
+        .unwrap_or(ParseErr(Nothing, line));
     Err(err)
 }
 
-fn parse_origin(mut expr: &str) -> Result<Vec<Statement>, ParseErr> {
+/// Parses a directive moving the solved figure or choosing a position
+/// (e.g. `position A = (1, 2)`, `orientation A B = π/2` or `branch C = 2`).
+fn parse_directive(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    let not_directive = ParseErr(Nothing, line);
+    let mut expr = line;
+    let branch = literal("branch")(&mut expr).is_ok();
+    let position = branch || literal("position")(&mut expr).is_ok();
+    if !position && literal("orientation")(&mut expr).is_err() {
+        return Err(not_directive);
+    }
+    // Points may share the name of a directive (e.g. `position = (1, 2)`).
+    if space(&mut expr).is_err() || word(&mut { expr }).is_err() {
+        return Err(not_directive);
+    }
+    let points = parse_points(&mut expr, if position { 1 } else { 2 })?;
+    space(&mut expr);
+    let value = if blank(expr) {
+        None
+    } else {
+        wrap(literal("=")(&mut expr), Expected(&["="]))?;
+        space(&mut expr);
+        Some(expr)
+    };
+    let s_type = if branch {
+        let Some(mut expr) = value else {
+            return Err(ParseErr(Expected(&["="]), expr));
+        };
+        let start = expr;
+        let n = wrap(
+            take_while(|c| c.is_ascii_digit(), 1, usize::MAX)(&mut expr),
+            Expected(&["number"]),
+        )?;
+        if !blank(expr) {
+            return Err(ParseErr(Extra, expr));
+        }
+        match n.parse() {
+            Ok(n) if n > 0 => StatementType::Branch(n),
+            _ => return Err(ParseErr(Invalid, start)),
+        }
+    } else if position {
+        StatementType::Position(match value {
+            Some(expr) => parse_vector(expr).map_err(|e| match e.0 {
+                Nothing => ParseErr(Expected(&["("]), e.1),
+                _ => e,
+            })?,
+            None => Vector::ZERO,
+        })
+    } else {
+        let m = parse_math(value.unwrap_or("0"))?;
+        // The figure cannot turn to match itself.
+        if !m.points.is_empty() {
+            return Err(ParseErr(Expected(&["number"]), value.unwrap_or_default()));
+        }
+        StatementType::Orientation(m)
+    };
+    Ok(vec![Statement::new(s_type, points)])
+}
+
+fn parse_origin(mut expr: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
     let p = wrap(word(&mut expr), Nothing)?;
     space(&mut expr);
     let v = if literal("=")(&mut expr).is_ok() {
-        parse_vector(expr.trim_start())?
-    } else {
+        parse_vector(expr.trim_start()).map_err(|e| match e.0 {
+            Nothing => ParseErr(Expected(&["("]), e.1),
+            _ => e,
+        })?
+    } else if blank(expr) {
         Vector::ZERO
+    } else {
+        return Err(ParseErr(Extra, expr));
     };
-    Ok(vec![Statement {
-        s_type: StatementType::Origin(v),
-        points: vec![p.to_string()],
-    }])
+    Ok(vec![Statement::new(
+        StatementType::Origin(v),
+        vec![p.to_string()],
+    )])
+}
+
+fn parse_parallel(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    Ok(parse_line_relation(line, PARALLEL)?
+        .windows(2)
+        .map(|pair| Statement::new(StatementType::Parallel, pair.concat()))
+        .collect())
+}
+
+fn parse_perpendicular(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    Ok(parse_line_relation(line, PERPENDICULAR)?
+        .windows(2)
+        .map(|pair| Statement::new(StatementType::Perpendicular, pair.concat()))
+        .collect())
 }
 
-fn parse_distance(expr: &mut &str) -> Result<Vec<String>, ParseErr> {
+/// Parses a chain of collinear points (e.g. `A-B-C-...`).
+///
+/// Every point after the first two is on the line through them. Any of
+/// the three may be the one placed (e.g. `C-A-B` places `C` from `A` and
+/// `B`).
+fn parse_collinear(mut line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    let not_collinear = ParseErr(Nothing, line);
+    space(&mut line);
+    let mut points = vec![wrap(word(&mut line), Nothing)?.to_string()];
+    while literal("-")(&mut line).is_ok() {
+        let Ok(p) = word(&mut line) else {
+            // Could be a path (e.g. `A-B->C`).
+            if !blank(line) {
+                return Err(not_collinear);
+            }
+            return Err(ParseErr(Expected(&["point"]), line));
+        };
+        points.push(p.to_string());
+    }
+    if !blank(line) || points.len() < 2 {
+        return Err(not_collinear);
+    }
+    if points.len() < 3 {
+        return Err(ParseErr(Expected(&["third point"]), line));
+    }
+    Ok(points[2..]
+        .iter()
+        .map(|p| {
+            Statement::new(
+                StatementType::Collinear,
+                vec![points[0].clone(), points[1].clone(), p.clone()],
+            )
+        })
+        .collect())
+}
+
+/// Parses a path of segments (e.g. `A→B`, `A-B→C` or `A-B-C→D`),
+/// written left-to-right or right-to-left (e.g. `B←A`).
+fn parse_path(mut line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    let not_path = ParseErr(Nothing, line);
+    space(&mut line);
+    let mut points = vec![word(&mut line).map_err(|_| not_path)?.to_string()];
+    // Whether each separator between points is an arrow, and where it is.
+    let mut separators = Vec::new();
+    let mut forward = None;
+    loop {
+        space(&mut line);
+        if blank(line) {
+            break;
+        }
+        let separator = line;
+        let arrow = if symbol(ARROW)(&mut line).is_ok() {
+            Some(true)
+        } else if symbol(LEFT_ARROW)(&mut line).is_ok() {
+            Some(false)
+        } else if literal("-")(&mut line).is_ok() {
+            None
+        } else if forward.is_none() {
+            return Err(not_path);
+        } else {
+            return Err(ParseErr(Extra, line));
+        };
+        if let Some(arrow) = arrow {
+            if *forward.get_or_insert(arrow) != arrow {
+                let expected: &[&str] = if forward == Some(true) {
+                    &[ARROW.0]
+                } else {
+                    &[LEFT_ARROW.0]
+                };
+                return Err(ParseErr(Expected(expected), separator));
+            }
+        }
+        separators.push((arrow.is_some(), separator));
+        space(&mut line);
+        points.push(wrap(word(&mut line), Expected(&["point"]))?.to_string());
+    }
+    // Without arrows this is not a path (e.g. `A-B-C`).
+    let Some(forward) = forward else {
+        return Err(not_path);
+    };
+    if !forward {
+        points.reverse();
+        separators.reverse();
+    }
+
+    let mut points = points.into_iter();
+    let start = points.next().unwrap();
+    let mut segments = Vec::new();
+    let mut controls = Vec::new();
+    let mut dangling = None;
+    for (point, (arrow, separator)) in points.zip(separators) {
+        if !arrow {
+            // Segments have at most two control points.
+            if controls.len() == 2 {
+                return Err(ParseErr(Expected(&[ARROW.0]), separator));
+            }
+            dangling.get_or_insert(separator);
+            controls.push(point);
+            continue;
+        }
+        dangling = None;
+        let mut controls = std::mem::take(&mut controls).into_iter();
+        segments.push(match (controls.next(), controls.next()) {
+            (None, _) => Segment::Line(point),
+            (Some(p0), None) => Segment::Quadratic(p0, point),
+            (Some(p0), Some(p1)) => Segment::Cubic(p0, p1, point),
+        });
+    }
+    if let Some(separator) = dangling {
+        return Err(ParseErr(Expected(&[ARROW.0]), separator));
+    }
+    let path = Path { start, segments };
+    let points = path.points().cloned().collect();
+    Ok(vec![Statement::new(StatementType::Path(path), points)])
+}
+
+/// Parses comma separated signed angles (e.g. `±∠A B C, ∓∠D E F, ...`).
+fn parse_chirality(line: &str) -> Result<Vec<Statement>, ParseErr<'_>> {
+    let mut chiralities = Vec::new();
+    let mut points = Vec::new();
+    for mut expr in line.split(",") {
+        space(&mut expr);
+        let chirality = if symbol(PRO)(&mut expr).is_ok() {
+            Chirality::Pro
+        } else if symbol(ANTI)(&mut expr).is_ok() {
+            Chirality::Anti
+        } else if chiralities.is_empty() {
+            return Err(ParseErr(Nothing, line));
+        } else {
+            return Err(ParseErr(Expected(&[PRO.0, ANTI.0]), expr));
+        };
+        points.append(&mut parse_angle(&mut expr).map_err(|e| match e.0 {
+            Nothing => ParseErr(Expected(&["angle"]), e.1),
+            _ => e,
+        })?);
+        if !blank(expr) {
+            return Err(ParseErr(Extra, expr));
+        }
+        chiralities.push(chirality);
+    }
+    if chiralities.len() < 2 {
+        return Err(ParseErr(Expected(&[","]), &line[line.len()..]));
+    }
+    Ok(vec![Statement::new(
+        StatementType::Chirality(chiralities),
+        points,
+    )])
+}
+
+/// Parses a chain of lines separated by a relation symbol
+/// (e.g. `A B ∥ C D ∥ ...`).
+fn parse_line_relation(mut expr: &str, relation: Symbol) -> Result<Vec<Vec<String>>, ParseErr<'_>> {
+    let not_relation = ParseErr(Nothing, expr);
+    let mut lines = vec![parse_points(&mut expr, 2).map_err(|_| not_relation)?];
+    loop {
+        space(&mut expr);
+        if blank(expr) {
+            break;
+        }
+        if symbol(relation)(&mut expr).is_err() {
+            return Err(if lines.len() == 1 {
+                not_relation
+            } else {
+                ParseErr(Extra, expr)
+            });
+        }
+        space(&mut expr);
+        lines.push(parse_points(&mut expr, 2)?);
+    }
+    if lines.len() < 2 {
+        return Err(not_relation);
+    }
+    Ok(lines)
+}
+
+/// Parses `n` space separated points.
+fn parse_points<'a>(expr: &mut &'a str, n: usize) -> Result<Vec<String>, ParseErr<'a>> {
+    let mut points = Vec::with_capacity(n);
+    for i in 0..n {
+        // Without a space there is no next point (e.g. `|A|`).
+        if i > 0 {
+            wrap(space(expr), Expected(&["point"]))?;
+        }
+        points.push(wrap(word(expr), Expected(&["point"]))?.to_string());
+    }
+    Ok(points)
+}
+
+fn parse_distance<'a>(expr: &mut &'a str) -> Result<Vec<String>, ParseErr<'a>> {
+    let open = *expr;
     wrap(literal("|")(expr), Nothing)?;
     space(expr);
-    let p0 = wrap(word(expr), No("point"))?;
-    wrap(space(expr), No("space"))?;
-    let p1 = wrap(word(expr), No("point"))?;
+    let p0 = wrap(word(expr), Expected(&["point"]))?;
+    wrap(space(expr), Expected(&["point"]))?;
+    let p1 = wrap(word(expr), Expected(&["point"]))?;
     space(expr);
-    wrap(literal("|")(expr), No("|"))?;
+    wrap(
+        literal("|")(expr),
+        Unclosed {
+            close: "|",
+            what: "distance",
+            open: &open[..open.len() - expr.len()],
+        },
+    )?;
     Ok(vec![p0.to_string(), p1.to_string()])
 }
 
-fn parse_orientation(expr: &mut &str) -> Result<Vec<String>, ParseErr> {
+fn parse_orientation<'a>(expr: &mut &'a str) -> Result<Vec<String>, ParseErr<'a>> {
+    let open = *expr;
     wrap(literal("<")(expr), Nothing)?;
     space(expr);
-    let p0 = wrap(word(expr), No("point"))?;
-    wrap(space(expr), No("space"))?;
-    let p1 = wrap(word(expr), No("point"))?;
+    let p0 = wrap(word(expr), Expected(&["point"]))?;
+    wrap(space(expr), Expected(&["point"]))?;
+    let p1 = wrap(word(expr), Expected(&["point"]))?;
     space(expr);
-    wrap(literal(">")(expr), No(">"))?;
+    wrap(
+        literal(">")(expr),
+        Unclosed {
+            close: ">",
+            what: "orientation",
+            open: &open[..open.len() - expr.len()],
+        },
+    )?;
     Ok(vec![p0.to_string(), p1.to_string()])
 }
 
+fn parse_angle<'a>(expr: &mut &'a str) -> Result<Vec<String>, ParseErr<'a>> {
+    wrap(symbol(ANGLE)(expr), Nothing)?;
+    space(expr);
+    let p0 = wrap(word(expr), Expected(&["point"]))?;
+    wrap(space(expr), Expected(&["point"]))?;
+    let p1 = wrap(word(expr), Expected(&["point"]))?;
+    wrap(space(expr), Expected(&["point"]))?;
+    let p2 = wrap(word(expr), Expected(&["point"]))?;
+    Ok(vec![p0.to_string(), p1.to_string(), p2.to_string()])
+}
+
 const fn take_while<'a>(
     mut f: impl FnMut(char) -> bool,
     min: usize,
     max: usize,
-) -> impl FnMut(&mut &'a str) -> Result<&'a str, *const u8> {
+) -> impl FnMut(&mut &'a str) -> Result<&'a str, &'a str> {
     move |input: &mut &'a str| {
         let mut chars = input.char_indices();
         let split = loop {
@@ -335,7 +2156,7 @@ const fn take_while<'a>(
             }
         };
         if split < min || split >= max {
-            return Err(input.as_ptr());
+            return Err(input);
         }
         let result = &input[..split];
         *input = &input[split..];
@@ -344,25 +2165,81 @@ const fn take_while<'a>(
 }
 
 #[inline]
-fn space<'a>(input: &mut &'a str) -> Result<&'a str, *const u8> {
+fn space<'a>(input: &mut &'a str) -> Result<&'a str, &'a str> {
     take_while(char::is_whitespace, 1, usize::MAX)(input)
 }
 
+/// Marks which may end a point name (e.g. `A′`).
+pub const PRIMES: [char; 4] = ['\'', '′', '″', '‴'];
+/// Constants which may not name points.
+/// `E` and `e` are allowed since they are common point names.
+const KEYWORDS: [&str; 8] = ["PI", "Pi", "pi", "π", "TAU", "Tau", "tau", "τ"];
+
+/// The first token of the input: a word, number or run of symbols.
+fn token(input: &str) -> &str {
+    let end = match input.chars().next() {
+        Some(c) if c.is_alphanumeric() => input
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || PRIMES.contains(&c))),
+        Some(_) => input.find(|c: char| c.is_alphanumeric() || c.is_whitespace()),
+        None => None,
+    };
+    &input[..end.unwrap_or(input.len())]
+}
+
+/// Parses a point name (e.g. `A`, `P1`, `A_2`, `A′` or `B₁`).
+fn word<'a>(input: &mut &'a str) -> Result<&'a str, &'a str> {
+    let mut chars = input.chars();
+    if !chars.next().is_some_and(char::is_alphabetic) {
+        return Err(input);
+    }
+    let rest = chars
+        .as_str()
+        .trim_start_matches(|c: char| c.is_alphanumeric() || c == '_' || PRIMES.contains(&c));
+    let (word, rest) = input.split_at(input.len() - rest.len());
+    if KEYWORDS.contains(&word) {
+        return Err(input);
+    }
+    *input = rest;
+    Ok(word)
+}
+
+/// Proper and improper spellings of a symbol.
+type Symbol = (&'static str, &'static str);
+pub(super) const ANGLE: Symbol = ("∠", "<");
+const PARALLEL: Symbol = ("∥", "||");
+const PERPENDICULAR: Symbol = ("⟂", "_|_");
+const PRO: Symbol = ("±", "+/-");
+const ANTI: Symbol = ("∓", "-/+");
+const ARROW: Symbol = ("→", "->");
+const LEFT_ARROW: Symbol = ("←", "<-");
+
+/// Accepts either spelling of the symbol.
 #[inline]
-fn word<'a>(input: &mut &'a str) -> Result<&'a str, *const u8> {
-    take_while(char::is_alphabetic, 1, usize::MAX)(input)
+fn symbol<'a>(symbol: Symbol) -> impl Fn(&mut &'a str) -> Result<&'a str, &'a str> {
+    move |i: &mut &'a str| literal(symbol.0)(i).or_else(|_| literal(symbol.1)(i))
 }
 
 #[inline]
-const fn literal<'a>(pattern: &'a str) -> impl Fn(&mut &'a str) -> Result<&'a str, *const u8> {
+const fn literal<'a>(pattern: &'a str) -> impl Fn(&mut &'a str) -> Result<&'a str, &'a str> {
     move |i: &mut &'a str| {
-        *i = i.strip_prefix(pattern).ok_or(i.as_ptr())?;
+        *i = i.strip_prefix(pattern).ok_or(*i)?;
         Ok(pattern)
     }
 }
 
+/// Restores the input if the parser fails.
+#[inline]
+pub(super) fn attempt<'a, T>(
+    mut f: impl FnMut(&mut &'a str) -> Result<T, ParseErr<'a>>,
+) -> impl FnMut(&mut &'a str) -> Result<T, ParseErr<'a>> {
+    move |input: &mut &'a str| {
+        let start = *input;
+        f(input).inspect_err(|_| *input = start)
+    }
+}
+
 #[inline]
-pub(super) fn wrap<T>(res: Result<T, *const u8>, t: ParseErrType) -> Result<T, ParseErr> {
+pub(super) fn wrap<'a, T>(res: Result<T, &'a str>, t: ParseErrType<'a>) -> Result<T, ParseErr<'a>> {
     res.map_err(|p| ParseErr(t, p))
 }
 
@@ -0,0 +1,72 @@
+use std::fmt::Display;
+
+/// A byte range within a document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    /// The span of `part` within `whole` if it is a slice of it.
+    pub fn within(whole: &str, part: &str) -> Option<Self> {
+        let start = (part.as_ptr() as usize).checked_sub(whole.as_ptr() as usize)?;
+        let end = start + part.len();
+        (end <= whole.len()).then_some(Self { start, end })
+    }
+    /// Moves the span forward by `offset` bytes.
+    pub fn offset(self, offset: usize) -> Self {
+        Self::new(self.start + offset, self.end + offset)
+    }
+    /// The line and column of the start of the span, counting from 1.
+    /// Columns count characters.
+    pub fn line_col(&self, document: &str) -> (usize, usize) {
+        let before = document.get(..self.start).unwrap_or(document);
+        let line = before.matches('\n').count() + 1;
+        let col = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        (line, col)
+    }
+}
+
+/// An error or warning about part of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<Note>,
+}
+/// Additional information attached to a [Diagnostic].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Note {
+    pub message: String,
+    pub span: Option<Span>,
+}
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            notes: Vec::new(),
+        }
+    }
+    pub fn with_note(mut self, message: impl Into<String>, span: Option<Span>) -> Self {
+        self.notes.push(Note {
+            message: message.into(),
+            span,
+        });
+        self
+    }
+}
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt::Display;
+
+use super::{Diagnostic, Span};
+
+/// A reason a figure cannot be built from its statements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FigureError {
+    /// A point is used but no statement places it.
+    UnknownPoint { point: String, spans: Vec<Span> },
+    /// Points that are not connected to any origin (e.g. `A = (0, 0)`).
+    NoOrigin {
+        points: Vec<String>,
+        spans: Vec<Span>,
+    },
+    /// A point with fewer constraints than it needs to be placed.
+    TooFewConstraints {
+        point: String,
+        count: usize,
+        spans: Vec<Span>,
+    },
+    /// Points which depend only on each other (e.g. `|A C| = |D C|` and
+    /// `|A D| = |C D|` with neither placed from another point), so none
+    /// can be placed.
+    Cycle {
+        points: Vec<String>,
+        spans: Vec<Span>,
+    },
+    /// Points solved together which no positions satisfy.
+    Unsolvable {
+        points: Vec<String>,
+        spans: Vec<Span>,
+    },
+    /// Points connected to placed points which still cannot be placed.
+    Unreachable {
+        points: Vec<String>,
+        spans: Vec<Span>,
+    },
+}
+impl FigureError {
+    /// The spans of the statements involved, in document order.
+    pub fn spans(&self) -> &[Span] {
+        match self {
+            Self::UnknownPoint { spans, .. }
+            | Self::NoOrigin { spans, .. }
+            | Self::TooFewConstraints { spans, .. }
+            | Self::Cycle { spans, .. }
+            | Self::Unsolvable { spans, .. }
+            | Self::Unreachable { spans, .. } => spans,
+        }
+    }
+}
+impl Display for FigureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownPoint { point, .. } => write!(f, "unknown point `{point}`"),
+            Self::NoOrigin { points, .. } => {
+                write!(f, "no origin places {}", points.join(", "))
+            }
+            Self::TooFewConstraints { point, count, .. } => {
+                write!(f, "`{point}` needs 2 constraints but has {count}")
+            }
+            Self::Cycle { points, .. } => match &points[..] {
+                [point] => write!(f, "`{point}` depends on itself"),
+                [rest @ .., last] => {
+                    let rest: Vec<_> = rest.iter().map(|p| format!("`{p}`")).collect();
+                    write!(f, "{} and `{last}` depend on each other", rest.join(", "))
+                }
+                [] => write!(f, "points depend on each other"),
+            },
+            Self::Unsolvable { points, .. } => {
+                write!(f, "cannot place {} together", points.join(", "))
+            }
+            Self::Unreachable { points, .. } => {
+                write!(
+                    f,
+                    "cannot place {} from the placed points",
+                    points.join(", ")
+                )
+            }
+        }
+    }
+}
+impl From<FigureError> for Diagnostic {
+    fn from(err: FigureError) -> Self {
+        let note = match err {
+            FigureError::UnknownPoint { .. } => "also used here",
+            FigureError::TooFewConstraints { .. } => "also constrained here",
+            FigureError::Cycle { .. } => "also part of the cycle",
+            FigureError::Unsolvable { .. } => "also solved together",
+            FigureError::NoOrigin { .. } | FigureError::Unreachable { .. } => "also stated here",
+        };
+        let first = err.spans().first().copied().unwrap_or_default();
+        let rest = err.spans().get(1..).unwrap_or_default();
+        let mut diagnostic = rest
+            .iter()
+            .fold(Diagnostic::new(err.to_string(), first), |d, span| {
+                d.with_note(note, Some(*span))
+            });
+        match err {
+            FigureError::NoOrigin { .. } => {
+                diagnostic = diagnostic.with_note("points are placed with `A = (x, y)`", None);
+            }
+            FigureError::Cycle { .. } => {
+                diagnostic = diagnostic.with_note(
+                    "at least one of them must be placed from another point",
+                    None,
+                );
+            }
+            FigureError::Unsolvable { .. } => {
+                diagnostic =
+                    diagnostic.with_note("no positions satisfy all of their statements", None);
+            }
+            _ => {}
+        }
+        diagnostic
+    }
+}
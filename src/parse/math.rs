@@ -1,25 +1,28 @@
 use std::{
     collections::VecDeque,
     f64::consts::{E, PI, TAU},
+    fmt::Display,
     hash::Hash,
     ops::{Add, Div, Mul, Sub},
 };
 
 use super::{
-    literal, parse_distance, parse_orientation, space, take_while, wrap, ParseErr,
+    attempt, literal, space, take_while, wrap, ParseErr,
     ParseErrType::{self, *},
-    QuantityType,
+    QuantityType, ANGLE,
 };
-use gsolve::math::{Number, Vector};
+use crate::solver::{Number, Vector};
 
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct MathExpr {
     expr: Vec<Math>,
     pub(super) points: Vec<String>,
 }
 impl MathExpr {
-    pub(super) fn func(&self) -> Result<Box<dyn Fn(&[Vector]) -> Number>, ParseErrType> {
+    pub(super) fn func(&self) -> Result<Box<dyn Fn(&[Vector]) -> Number>, ParseErrType<'static>> {
         let mut stack: VecDeque<Box<dyn Fn(&[Vector]) -> f64>> = VecDeque::new();
+        // Offset of each quantity's points within the expression's points.
+        let mut offset = 0;
 
         for m in &self.expr {
             match m {
@@ -28,12 +31,21 @@ impl MathExpr {
                         let n = *n;
                         Box::new(move |_| n)
                     }
-                    Operand::Quantity(t, _) => match t {
-                        QuantityType::Distance => Box::new(|pos| -> Number { pos[0].dist(pos[1]) }),
-                        QuantityType::Orientation => {
-                            Box::new(|pos| -> Number { (pos[1] - pos[0]).angle() })
+                    Operand::Quantity(t, points) => {
+                        let i = offset;
+                        offset += points.len();
+                        match t {
+                            QuantityType::Distance => {
+                                Box::new(move |pos| -> Number { pos[i].dist(pos[i + 1]) })
+                            }
+                            QuantityType::Orientation => {
+                                Box::new(move |pos| -> Number { (pos[i + 1] - pos[i]).angle() })
+                            }
+                            QuantityType::Angle => Box::new(move |pos| -> Number {
+                                unsigned_angle(pos[i], pos[i + 1], pos[i + 2])
+                            }),
                         }
-                    },
+                    }
                 }),
                 Math::Operator(op) => {
                     let rhs = stack.pop_front().ok_or(Invalid)?;
@@ -49,16 +61,58 @@ impl MathExpr {
         Ok(stack.pop_front().unwrap())
     }
 }
-#[derive(Debug, Clone, Hash)]
+impl Display for MathExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Rebuild infix notation, keeping each operand's precedence.
+        let mut stack: Vec<(String, Option<u8>)> = Vec::new();
+        for m in &self.expr {
+            match m {
+                Math::Operand(o) => stack.push((o.to_string(), None)),
+                Math::Operator(op) => {
+                    let (Some(rhs), Some(lhs)) = (stack.pop(), stack.pop()) else {
+                        return Err(std::fmt::Error);
+                    };
+                    let p = op.precedence();
+                    let wrap = |(s, q): (String, Option<u8>), tie: bool| match q {
+                        Some(q) if q < p.unwrap() || (q == p.unwrap() && tie) => {
+                            format!("({s})")
+                        }
+                        _ => s,
+                    };
+                    let lhs = wrap(lhs, op.is_r_assoc());
+                    let rhs = wrap(rhs, !op.is_r_assoc());
+                    stack.push((format!("{lhs} {op} {rhs}"), p));
+                }
+            }
+        }
+        match &stack[..] {
+            [(s, _)] => write!(f, "{s}"),
+            _ => Err(std::fmt::Error),
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum Math {
     Operand(Operand),
     Operator(Op),
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Operand {
     Constant(Number),
     Quantity(QuantityType, Vec<String>),
 }
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Constant(n) => write!(f, "{n}"),
+            Operand::Quantity(t, points) => match t {
+                QuantityType::Distance => write!(f, "|{}|", points.join(" ")),
+                QuantityType::Orientation => write!(f, "<{}>", points.join(" ")),
+                QuantityType::Angle => write!(f, "{}{}", ANGLE.0, points.join(" ")),
+            },
+        }
+    }
+}
 impl Hash for Operand {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
@@ -71,64 +125,104 @@ impl Hash for Operand {
     }
 }
 
+/// The angle at `b` between `a` and `c`, from 0 to π.
+pub(super) fn unsigned_angle(a: Vector, b: Vector, c: Vector) -> Number {
+    let angle = ((c - b).angle() - (a - b).angle()).rem_euclid(TAU);
+    angle.min(TAU - angle)
+}
+
 // https://mathcenter.oxford.emory.edu/site/cs171/shuntingYardAlgorithm/
-pub(super) fn parse_math(mut expr: &str) -> Result<MathExpr, ParseErr> {
+pub(super) fn parse_math(mut expr: &str) -> Result<MathExpr, ParseErr<'_>> {
     let mut output = Vec::new();
     let mut stack: Vec<Op> = Vec::new();
+    // Where each open parenthesis on the stack was opened.
+    let mut opened = Vec::new();
 
     'parsing: loop {
-        while let Some(op) = parse_op(&mut expr) {
-            if op != Op::LPn {
-                return Err(ParseErr(Invalid, expr.as_ptr()));
+        loop {
+            let start = expr;
+            match parse_op(&mut expr) {
+                Some(Op::LPn) => opened.push(start),
+                Some(_) => return Err(ParseErr(Expected(OPERAND), start)),
+                None => break,
             }
-            stack.push(op);
+            stack.push(Op::LPn);
             space(&mut expr);
         }
-        output.push(Math::Operand(
-            parse_distance(&mut expr)
-                .map(|p| Operand::Quantity(QuantityType::Distance, p))
-                .or_else(|_| {
-                    parse_orientation(&mut expr)
-                        .map(|p| Operand::Quantity(QuantityType::Orientation, p))
-                })
-                .or_else(|_| parse_number(&mut expr).map(|n| Operand::Constant(n)))?,
-        ));
+        let start = expr;
+        let mut err = ParseErr(Nothing, start);
+        let mut operand = None;
+        for qt in [
+            QuantityType::Distance,
+            QuantityType::Orientation,
+            QuantityType::Angle,
+        ] {
+            match attempt(qt.parser())(&mut expr) {
+                Ok(points) => {
+                    operand = Some(Operand::Quantity(qt, points));
+                    break;
+                }
+                // Keep the first error that got furthest.
+                Err(e) if err.0 == Nothing || e.1.len() < err.1.len() => err = e,
+                Err(_) => {}
+            }
+        }
+        let operand = match operand {
+            Some(operand) => operand,
+            None if err.0 != Nothing => return Err(err),
+            None => parse_number(&mut expr)
+                .map(Operand::Constant)
+                .map_err(|e| match e.0 {
+                    Nothing if output.is_empty() && opened.is_empty() => e,
+                    Nothing => ParseErr(Expected(OPERAND), e.1),
+                    _ => e,
+                })?,
+        };
+        output.push(Math::Operand(operand));
         space(&mut expr);
         let op = loop {
+            let start = expr;
             let Some(op) = parse_op(&mut expr) else {
                 if !expr.is_empty() {
-                    return Err(ParseErr(Extra, expr.as_ptr()));
+                    return Err(ParseErr(Expected(OPERATOR), expr));
                 } else {
                     break 'parsing;
                 }
             };
             if op == Op::RPn {
                 loop {
-                    let op = stack.pop().ok_or(ParseErr(No("("), expr.as_ptr()))?;
+                    let op = stack.pop().ok_or(ParseErr(Extra, start))?;
                     if op == Op::LPn {
+                        opened.pop();
                         break;
                     }
                     output.push(Math::Operator(op));
                 }
+                space(&mut expr);
+            } else if op == Op::LPn {
+                return Err(ParseErr(Expected(OPERATOR), start));
             } else {
                 break op;
             }
         };
         space(&mut expr);
-        match op {
-            Op::LPn | Op::RPn => {
-                return Err(ParseErr(Invalid, expr.as_ptr()));
-            }
-            _ => {
-                while stack
-                    .last()
-                    .is_some_and(|&o| o != Op::LPn && (op < o || (op == o && !op.is_r_assoc())))
-                {
-                    output.push(Math::Operator(stack.pop().unwrap()));
-                }
-                stack.push(op);
-            }
+        while stack
+            .last()
+            .is_some_and(|&o| o != Op::LPn && (op < o || (op <= o && !op.is_r_assoc())))
+        {
+            output.push(Math::Operator(stack.pop().unwrap()));
         }
+        stack.push(op);
+    }
+    if let Some(open) = opened.pop() {
+        return Err(ParseErr(
+            Unclosed {
+                close: ")",
+                what: "parenthesis",
+                open: &open[..open.len() - expr.len()],
+            },
+            expr,
+        ));
     }
     for op in stack.into_iter().rev() {
         output.push(Math::Operator(op));
@@ -151,26 +245,34 @@ pub(super) fn parse_math(mut expr: &str) -> Result<MathExpr, ParseErr> {
     })
 }
 
-pub(super) fn parse_vector(mut expr: &str) -> Result<Vector, ParseErr> {
+pub(super) fn parse_vector(mut expr: &str) -> Result<Vector, ParseErr<'_>> {
+    let open = expr;
     wrap(literal("(")(&mut expr), Nothing)?;
     space(&mut expr);
     let x = parse_number(&mut expr).map_err(|e| match e.0 {
-        Nothing => ParseErr(No("number"), e.1),
+        Nothing => ParseErr(Expected(&["number"]), e.1),
         _ => ParseErr(Invalid, e.1),
     })?;
     space(&mut expr);
-    wrap(literal(",")(&mut expr), No(","))?;
+    wrap(literal(",")(&mut expr), Expected(&[","]))?;
     space(&mut expr);
     let y = parse_number(&mut expr).map_err(|e| match e.0 {
-        Nothing => ParseErr(No("number"), e.1),
+        Nothing => ParseErr(Expected(&["number"]), e.1),
         _ => ParseErr(Invalid, e.1),
     })?;
     space(&mut expr);
-    wrap(literal(")")(&mut expr), No(")"))?;
+    wrap(
+        literal(")")(&mut expr),
+        Unclosed {
+            close: ")",
+            what: "vector",
+            open: &open[..open.len() - expr.len()],
+        },
+    )?;
     Ok(Vector { x, y })
 }
 
-pub(super) fn parse_number(expr: &mut &str) -> Result<Number, ParseErr> {
+pub(super) fn parse_number<'a>(expr: &mut &'a str) -> Result<Number, ParseErr<'a>> {
     if literal("PI")(expr).is_ok()
         || literal("Pi")(expr).is_ok()
         || literal("pi")(expr).is_ok()
@@ -188,6 +290,7 @@ pub(super) fn parse_number(expr: &mut &str) -> Result<Number, ParseErr> {
     if literal("E")(expr).is_ok() || literal("e")(expr).is_ok() {
         return Ok(E);
     }
+    let start = *expr;
     let mut n = literal("+")(expr)
         .or_else(|_| literal("-")(expr))
         .unwrap_or_default()
@@ -196,9 +299,14 @@ pub(super) fn parse_number(expr: &mut &str) -> Result<Number, ParseErr> {
         take_while(|c| c.is_ascii_digit() || c == '.', 1, usize::MAX)(expr),
         Nothing,
     )?);
-    n.parse().map_err(|_| ParseErr(Invalid, expr.as_ptr()))
+    n.parse().map_err(|_| ParseErr(Invalid, start))
 }
 
+/// What may start an operand.
+const OPERAND: &[&str] = &["number", "quantity", "("];
+/// What may follow an operand.
+const OPERATOR: &[&str] = &["+", "-", "*", "/", "^", ")"];
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Op {
     Add,
@@ -235,6 +343,20 @@ impl Op {
         }
     }
 }
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "×",
+            Op::Div => "÷",
+            Op::Pow => "^",
+            Op::LPn => "(",
+            Op::RPn => ")",
+        };
+        write!(f, "{op}")
+    }
+}
 impl PartialOrd for Op {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         self.precedence().partial_cmp(&other.precedence())
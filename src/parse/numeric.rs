@@ -0,0 +1,97 @@
+use crate::solver::Number;
+
+/// Largest residual accepted as a solution.
+pub(super) const TOLERANCE: Number = 1e-6;
+
+/// Finds `x` where every residual of `f` is zero, starting from `x`
+/// (Levenberg–Marquardt with a finite difference Jacobian).
+pub(super) fn least_squares(
+    mut x: Vec<Number>,
+    f: impl Fn(&[Number]) -> Vec<Number>,
+) -> Option<Vec<Number>> {
+    let cost = |r: &[Number]| r.iter().map(|r| r * r).sum::<Number>();
+    let mut r = f(&x);
+    let mut c = cost(&r);
+    let mut damping = 1e-3;
+    for _ in 0..200 {
+        // Refine well past the tolerance while the cost still falls.
+        if !c.is_finite() || c < TOLERANCE.powi(4) {
+            break;
+        }
+        // Jacobian by forward differences.
+        let jacobian: Vec<Vec<Number>> = (0..x.len())
+            .map(|j| {
+                let h = 1e-7 * x[j].abs().max(1.);
+                let mut xh = x.clone();
+                xh[j] += h;
+                f(&xh).iter().zip(&r).map(|(rh, r)| (rh - r) / h).collect()
+            })
+            .collect();
+        // Normal equations JᵀJ δ = -Jᵀr.
+        let jtj: Vec<Vec<Number>> = jacobian
+            .iter()
+            .map(|a| {
+                jacobian
+                    .iter()
+                    .map(|b| a.iter().zip(b).map(|(a, b)| a * b).sum())
+                    .collect()
+            })
+            .collect();
+        let jtr: Vec<Number> = jacobian
+            .iter()
+            .map(|a| -a.iter().zip(&r).map(|(a, r)| a * r).sum::<Number>())
+            .collect();
+        // Raise the damping until a step lowers the cost.
+        let mut improved = false;
+        while damping < 1e12 {
+            let mut a = jtj.clone();
+            for (i, row) in a.iter_mut().enumerate() {
+                row[i] += damping * (row[i] + 1.);
+            }
+            if let Some(step) = solve_linear(a, jtr.clone()) {
+                let xs: Vec<Number> = x.iter().zip(&step).map(|(x, s)| x + s).collect();
+                let rs = f(&xs);
+                let cs = cost(&rs);
+                if cs < c {
+                    (x, r, c) = (xs, rs, cs);
+                    damping = (damping / 10.).max(1e-12);
+                    improved = true;
+                    break;
+                }
+            }
+            damping *= 10.;
+        }
+        if !improved {
+            break;
+        }
+    }
+    r.iter().all(|r| r.abs() < TOLERANCE).then_some(x)
+}
+
+/// Solves `a x = b` by Gaussian elimination with partial pivoting.
+fn solve_linear(mut a: Vec<Vec<Number>>, mut b: Vec<Number>) -> Option<Vec<Number>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-300 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (top, rows) = a.split_at_mut(col + 1);
+        let (top_b, rows_b) = b.split_at_mut(col + 1);
+        for (row, b_row) in rows.iter_mut().zip(rows_b) {
+            let factor = row[col] / top[col][col];
+            for (x, t) in row[col..].iter_mut().zip(&top[col][col..]) {
+                *x -= factor * t;
+            }
+            *b_row -= factor * top_b[col];
+        }
+    }
+    let mut x = vec![0.; n];
+    for row in (0..n).rev() {
+        let sum: Number = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
@@ -0,0 +1,373 @@
+use super::*;
+
+/// Builds the figure of a document which parses without errors.
+fn figure(document: &str) -> Result<Figure, Vec<FigureError>> {
+    let (statements, diagnostics) = parse(document);
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    Figure::from_statements(statements)
+}
+
+#[test]
+fn comments_are_not_constraints() {
+    let fig = figure("\"x\"\nA = (0, 0)\n").unwrap();
+    assert_eq!(fig.solve().unwrap(), vec![Vector::ZERO]);
+}
+
+#[test]
+fn freedom_skips_comments() {
+    let (statements, _) = parse("\"x\"\nA = (0, 0)\n|A B| = 1\n");
+    assert_eq!(
+        Figure::freedom(&statements),
+        vec![
+            ("A".to_string(), Freedom::Determined),
+            ("B".to_string(), Freedom::Curve),
+        ]
+    );
+}
+
+#[test]
+fn clusters_solve_together() {
+    // `C` and `D` each depend on the other.
+    let fig =
+        figure("A = (0, 0)\n|A B| = 1\n<A B> = 0\n|B C| = |D C| = 1\n|A D| = |C D| = 1\n").unwrap();
+    assert_eq!(fig.clusters.len(), 1);
+    let pos = fig.solve().unwrap();
+    assert!(fig.unsatisfied(&pos).is_empty());
+}
+
+#[test]
+fn statements_place_their_unplaced_point() {
+    let fig = figure(
+        "A = (0, 0)\nB = (2, 0)\nC = (0, 1)\n\
+         D C ∥ A B\n|C D| = 2\n\
+         E C ⟂ A B\n|E C| = 1\n\
+         <F A> = π/2\n|F A| = 1\n\
+         <G A B = π/2\n|A G| = 1\n",
+    )
+    .unwrap();
+    let pos = fig.solve().unwrap();
+    assert!(fig.unsatisfied(&pos).is_empty());
+    assert!(fig.conflicts(&pos).is_empty());
+    let [d, e, f] = ["D", "E", "F"].map(|p| pos[fig.point_map[p]]);
+    assert!((d.y - 1.).abs() < 1e-9 && (d.x.abs() - 2.).abs() < 1e-9);
+    assert!(e.x.abs() < 1e-9);
+    assert!(f.dist(Vector { x: 0., y: -1. }) < 1e-9);
+    let g = pos[fig.point_map["G"]];
+    assert!(g.x.abs() < 1e-9 && (g.y.abs() - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn collinear_chains_place_any_point() {
+    let fig = figure("A = (0, 0)\nB = (2, 0)\nC-A-B\n|A C| = 1\nA-D-B\n|B D| = 1\n").unwrap();
+    let pos = fig.solve().unwrap();
+    assert!(fig.unsatisfied(&pos).is_empty());
+    let [c, d] = ["C", "D"].map(|p| pos[fig.point_map[p]]);
+    assert!(c.y.abs() < 1e-9 && (c.x.abs() - 1.).abs() < 1e-9);
+    assert!(d.y.abs() < 1e-9 && ((d.x - 2.).abs() - 1.).abs() < 1e-9);
+}
+
+#[test]
+fn figures_without_an_origin_keep_every_constraint() {
+    let fig = figure("|A B| = |B C| = |C A| = 1\n").unwrap();
+    let pos = fig.solve().unwrap();
+    assert!(fig.unsatisfied(&pos).is_empty());
+    assert!(fig.conflicts(&pos).is_empty());
+    let [a, b] = ["A", "B"].map(|p| pos[fig.point_map[p]]);
+    assert_eq!(a, Vector::ZERO);
+    assert!(b.dist(Vector { x: 1., y: 0. }) < 1e-9);
+}
+
+#[test]
+fn unanchored_cycles_are_reported() {
+    let errors = figure(
+        "A = (0, 0)\n|C B| = |D B| = 1\n|B C| = |D C| = 1\n|B D| = |C D| = 1\n\
+         |F E| = |G E| = 1\n|E F| = |G F| = 1\n|E G| = |F G| = 1\n",
+    )
+    .err()
+    .unwrap();
+    let cycles: Vec<_> = errors
+        .iter()
+        .map(|err| match err {
+            FigureError::Cycle { points, spans } => (points.clone(), spans.len()),
+            _ => panic!("{err:?}"),
+        })
+        .collect();
+    assert_eq!(
+        cycles,
+        [
+            (vec!["B".into(), "C".into(), "D".into()], 6),
+            (vec!["E".into(), "F".into(), "G".into()], 6)
+        ]
+    );
+}
+
+#[test]
+fn fixing_statements_are_not_constraints() {
+    let fig = figure("|A B| = 1\n|A C| = |B C| = 1\n").unwrap();
+    let constraints: Vec<String> = fig.constraints.iter().map(|(s, _)| s.to_string()).collect();
+    assert_eq!(constraints, ["|A B| = 1", "|A C| = 1", "|B C| = 1"]);
+    let pos = fig.solve().unwrap();
+    assert_eq!(fig.verify(&pos).len(), 3);
+}
+
+#[test]
+fn extra_constraints_choose_positions() {
+    let fig = figure(
+        "A = (0, 0)\nB = (3, 0)\n|A C| = 2\n|B D| = 2\n|C D| = 3\n\
+         |A D| = 3.605551\n|B C| = 3.605551\n",
+    )
+    .unwrap();
+    let pos = fig.solve().unwrap();
+    assert!(fig.conflicts(&pos).is_empty());
+    // `C` may be mirrored, but `|A D|` leaves `D` one position.
+    let points: Vec<String> = fig.branches().into_iter().map(|b| b.point).collect();
+    assert_eq!(points, ["C"]);
+}
+
+#[test]
+fn chirality_chooses_positions() {
+    let fig = figure(
+        "A = (0, 0)\nB = (1, 0)\n|A C| = 1\n|B C| = 1\n|A D| = 1\n|B D| = 1\n\
+         ±∠B A C, ∓∠B A D\n",
+    )
+    .unwrap();
+    let pos = fig.solve().unwrap();
+    let [c, d] = ["C", "D"].map(|p| pos[fig.point_map[p]]);
+    assert!(c.y * d.y < 0.);
+}
+
+#[test]
+fn chirality_scales_with_groups() {
+    // Every group is solved in one attempt, where trying every combination
+    // of signs would not finish.
+    let mut document = String::from("A = (0, 0)\nB = (1, 0)\n");
+    for i in 0..80 {
+        document += &format!("|A C{i}| = |B C{i}| = 1\n|A D{i}| = |B D{i}| = 1\n");
+        document += &format!("+/-<B A C{i}, -/+<B A D{i}\n");
+    }
+    let fig = figure(&document).unwrap();
+    let pos = fig.solve().unwrap();
+    for i in 0..80 {
+        let [c, d] = [format!("C{i}"), format!("D{i}")].map(|p| pos[fig.point_map[&p]]);
+        assert!(c.y * d.y < 0.);
+    }
+}
+
+/// Parses a line which must hold exactly one statement.
+fn statement(line: &str) -> Statement {
+    let statements = parse_line(line).unwrap();
+    assert_eq!(statements.len(), 1, "{line}");
+    statements.into_iter().next().unwrap()
+}
+
+#[test]
+fn improper_spellings_match_proper_ones() {
+    for (proper, improper) in [
+        ("∠A B C = 1", "<A B C = 1"),
+        ("A B ∥ C D", "A B || C D"),
+        ("A B ⟂ C D", "A B _|_ C D"),
+        ("A→B", "A->B"),
+        ("A-B→C", "A-B->C"),
+        ("A-B-C→D", "A-B-C->D"),
+        ("±∠A B C, ∓∠B C D", "+/-<A B C, -/+<B C D"),
+        ("|A B| = ∠C D E", "|A B| = <C D E"),
+    ] {
+        assert_eq!(statement(proper), statement(improper), "{proper}");
+    }
+}
+
+#[test]
+fn statements_display_as_they_parse() {
+    for line in [
+        "A = (1, 2)",
+        "|A B| = 1",
+        "|A B| = 2 * |C D| + 1",
+        "<A B> = π/2",
+        "∠A B C = 1",
+        "A B ∥ C D",
+        "A B ⟂ C D",
+        "A-B-C",
+        "±∠A B C, ∓∠B C D",
+        "A→B-C→D-E-F→A",
+        "position A = (1, 2)",
+        "orientation A B = 1",
+        "branch C = 2",
+        "\"comment\"",
+    ] {
+        let parsed = statement(line);
+        assert_eq!(statement(&parsed.to_string()), parsed, "{line}");
+    }
+}
+
+#[test]
+fn left_arrows_are_reversed_paths() {
+    for (left, right) in [
+        ("B<-A", "A->B"),
+        ("B←A", "A→B"),
+        ("C<-B-A", "A-B->C"),
+        ("D<-C-B-A<-E", "E->A-B-C->D"),
+    ] {
+        assert_eq!(statement(left), statement(right), "{left}");
+    }
+    // A leading `<` is an angle, not an arrow.
+    let angle = statement("<A B C = 1");
+    assert!(matches!(
+        angle.s_type,
+        StatementType::Quantity(QuantityType::Angle, _)
+    ));
+    assert_eq!(angle.points, ["A", "B", "C"]);
+}
+
+#[test]
+fn paths_keep_one_direction() {
+    for (line, message, found) in [
+        ("A->B<-C", "expected `→`, found `<-`", 4),
+        ("A<-B->C", "expected `←`, found `->`", 4),
+        ("A-B<-C->D", "expected `←`, found `->`", 6),
+    ] {
+        let (statements, diagnostics) = parse(line);
+        assert!(statements.is_empty(), "{line}");
+        assert_eq!(diagnostics.len(), 1, "{line}");
+        assert_eq!(diagnostics[0].message, message);
+        assert_eq!(diagnostics[0].span.start, found, "{line}");
+    }
+}
+
+#[test]
+fn example_solves() {
+    let fig = figure(include_str!("../../test.gcad")).unwrap();
+    let pos = fig.solve().unwrap();
+    assert!(fig.unsatisfied(&pos).is_empty());
+    assert!(fig.conflicts(&pos).is_empty());
+    // An "A" standing on L R, its inner points between the apex and the base.
+    let at = |p: &str| pos[fig.point_map[p]];
+    let (l, r, t) = (at("L"), at("R"), at("T"));
+    for p in ["LLL", "RRR", "TT", "TTT", "TL", "TR"] {
+        let p = at(p);
+        assert!(l.x < p.x && p.x < r.x, "{p}");
+        assert!(
+            t.y.min(l.y) - 1e-9 < p.y && p.y < t.y.max(l.y) + 1e-9,
+            "{p}"
+        );
+    }
+}
+
+#[test]
+fn contradictory_chirality_is_reported() {
+    let fig = figure("A = (0, 0)\nB = (1, 0)\n|A C| = |B C| = 1\n±∠B A C, ∓∠B A C\n").unwrap();
+    let err = fig.solve().unwrap_err();
+    assert_eq!(err.message, "no solution satisfies ±∠B A C, ∓∠B A C");
+}
+
+#[test]
+fn contradictory_placements_name_the_point() {
+    for (document, point, lines) in [
+        ("A = (0, 0)\n|A B| = 1\n|A B| = 2\n<A B> = 0\n", "B", [2, 3]),
+        // Too far apart for a triangle.
+        (
+            "A = (0, 0)\n|A B| = 3\n<A B> = 0\n|A C| = 1\n|B C| = 1\n",
+            "C",
+            [4, 5],
+        ),
+    ] {
+        let err = figure(document).unwrap().solve().unwrap_err();
+        assert_eq!(err.message, format!("no position satisfies `{point}`"));
+        assert_eq!(err.span.line_col(document).0, lines[0]);
+        let notes: Vec<usize> = err
+            .notes
+            .iter()
+            .map(|n| n.span.unwrap().line_col(document).0)
+            .collect();
+        assert_eq!(notes, lines);
+    }
+}
+
+#[test]
+fn point_names() {
+    for name in ["P1", "H12", "A_2", "A′", "A''", "B₁", "α"] {
+        let mut input = name;
+        assert_eq!(word(&mut input), Ok(name));
+        assert!(input.is_empty(), "{name}");
+    }
+    for name in ["pi", "π", "1A", "_A", "′"] {
+        let mut input = name;
+        assert!(word(&mut input).is_err(), "{name}");
+    }
+    assert_eq!(statement("|P1 A_2| = 1").points, ["P1", "A_2"]);
+}
+
+#[test]
+fn parse_errors_say_what_was_expected() {
+    for (line, message, column) in [
+        // Distances
+        (
+            "|A B = 1",
+            "expected `|` to close distance `|A B`, found `=`",
+            5,
+        ),
+        ("|A| = 1", "expected point, found `|`", 2),
+        ("|A B| = ", "expected value, found end of line", 8),
+        // Orientations
+        (
+            "<A B = 1",
+            "expected `>` to close orientation `<A B`, found `=`",
+            5,
+        ),
+        ("<A> = 1", "expected point, found `>`", 2),
+        // Vectors
+        (
+            "A = (1, 2",
+            "expected `)` to close vector `(1, 2`, found end of line",
+            9,
+        ),
+        ("A = (1 2)", "expected `,`, found `2`", 7),
+        ("A = (1, x)", "expected number, found `x`", 8),
+        // Math
+        (
+            "|A B| = 1 +",
+            "expected number, quantity or `(`, found end of line",
+            11,
+        ),
+        (
+            "|A B| = (1 + 2",
+            "expected `)` to close parenthesis `(1 + 2`, found end of line",
+            14,
+        ),
+        (
+            "|A B| = 2 $ 3",
+            "expected `+`, `-`, `*`, `/`, `^` or `)`, found `$`",
+            10,
+        ),
+        // Relations
+        ("A B || C", "expected point, found end of line", 8),
+    ] {
+        let (_, diagnostics) = parse(line);
+        assert_eq!(diagnostics.len(), 1, "{line}");
+        assert_eq!(diagnostics[0].message, message, "{line}");
+        assert_eq!(diagnostics[0].span.start, column, "{line}");
+    }
+}
+
+#[test]
+fn closed_angles_hint_at_the_angle() {
+    let (_, diagnostics) = parse("<A B C> = 1");
+    let notes: Vec<&str> = diagnostics[0]
+        .notes
+        .iter()
+        .map(|n| &n.message[..])
+        .collect();
+    assert_eq!(notes, ["did you mean `∠A B C`?"]);
+}
+
+#[test]
+fn every_bad_line_is_reported() {
+    let document = "A = (0, 0)\n|A B = 1\n|A B| = 1\n<A B = 1\n<A B> = 0\nA = (1, 2\n";
+    let (statements, diagnostics) = parse(document);
+    let statements: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+    assert_eq!(statements, ["A = (0, 0)", "|A B| = 1", "<A B> = 0"]);
+    let lines: Vec<usize> = diagnostics
+        .iter()
+        .map(|d| d.span.line_col(document).0)
+        .collect();
+    assert_eq!(lines, [2, 4, 6]);
+}
@@ -0,0 +1,144 @@
+use std::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::{Add, Sub},
+};
+
+mod construct;
+
+pub use construct::intersections;
+
+pub type Number = f64;
+/// The index of a point added to a solver.
+pub type PID = usize;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Vector {
+    pub x: Number,
+    pub y: Number,
+}
+impl Vector {
+    pub const ZERO: Vector = Vector { x: 0., y: 0. };
+    /// The unit vector at an angle from the x axis.
+    pub fn from_angle(angle: Number) -> Self {
+        Vector {
+            x: angle.cos(),
+            y: angle.sin(),
+        }
+    }
+    /// The angle from the x axis, in `(-π, π]`.
+    pub fn angle(&self) -> Number {
+        self.y.atan2(self.x)
+    }
+    pub fn dist(&self, other: Vector) -> Number {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+impl Add for Vector {
+    type Output = Vector;
+    fn add(self, rhs: Vector) -> Vector {
+        Vector {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+impl Sub for Vector {
+    type Output = Vector;
+    fn sub(self, rhs: Vector) -> Vector {
+        Vector {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+impl Hash for Vector {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x.to_be_bytes().hash(state);
+        self.y.to_be_bytes().hash(state);
+    }
+}
+impl Display for Vector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// A shape a point lies on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Geo {
+    Point(Vector),
+    /// A center and radius.
+    Circle(Vector, Number),
+    /// An origin and direction.
+    Ray(Vector, Vector),
+    /// A point on the line and its direction.
+    Line(Vector, Vector),
+}
+
+/// Maps the positions of a quantity's points to the alternative shapes a
+/// point may lie on.
+pub type QuantityFn = Box<dyn Fn(&[Vector]) -> Vec<Geo>>;
+
+/// The shapes a point may lie on given the positions of other points.
+pub struct Quantity {
+    pub func: QuantityFn,
+    pub points: Vec<PID>,
+}
+
+/// Places points one at a time from the quantities constraining them.
+///
+/// Backends only sequence points. [Figure](crate::parse::Figure)
+/// intersects the shapes of each point and chooses among the positions
+/// itself (see [intersections]), so the quantities it adds give a single
+/// [Geo::Point].
+pub trait SolverBackend {
+    /// Adds a point on every quantity, returning its index. Quantities
+    /// may only use points added before.
+    fn add_point(&mut self, quantities: Vec<Quantity>) -> PID;
+    /// The position of every point, by index.
+    fn solve(&self) -> Result<Vec<Vector>, NoPosition>;
+}
+/// No position satisfies every quantity of the point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoPosition(pub PID);
+impl Display for NoPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no position satisfies point {}", self.0)
+    }
+}
+impl Default for Box<dyn SolverBackend> {
+    fn default() -> Self {
+        Box::new(Construction::default())
+    }
+}
+
+/// The built-in backend, placing each point at the first intersection of
+/// its quantities.
+#[derive(Default)]
+pub struct Construction {
+    points: Vec<Vec<Quantity>>,
+}
+impl SolverBackend for Construction {
+    fn add_point(&mut self, quantities: Vec<Quantity>) -> PID {
+        self.points.push(quantities);
+        self.points.len() - 1
+    }
+    fn solve(&self) -> Result<Vec<Vector>, NoPosition> {
+        let mut pos: Vec<Vector> = Vec::with_capacity(self.points.len());
+        for (i, quantities) in self.points.iter().enumerate() {
+            let sets: Vec<Vec<Geo>> = quantities
+                .iter()
+                .map(|q| {
+                    let known: Vec<Vector> = q.points.iter().map(|&p| pos[p]).collect();
+                    (q.func)(&known)
+                })
+                .collect();
+            let Some(&p) = intersections(&sets).first() else {
+                return Err(NoPosition(i));
+            };
+            pos.push(p);
+        }
+        Ok(pos)
+    }
+}
@@ -0,0 +1,142 @@
+use super::{Geo, Number, Vector};
+
+/// Distance under which positions are the same.
+const EPSILON: Number = 1e-9;
+
+/// Finds the positions in every set of shapes, each set being the
+/// alternatives a quantity allows (e.g. both rays of an angle).
+pub fn intersections(sets: &[Vec<Geo>]) -> Vec<Vector> {
+    let Some((first, rest)) = sets.split_first() else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<Vector> = match rest.first() {
+        Some(second) => first
+            .iter()
+            .flat_map(|a| second.iter().flat_map(move |b| intersect(a, b)))
+            .collect(),
+        None => first
+            .iter()
+            .filter_map(|g| match g {
+                Geo::Point(v) => Some(*v),
+                _ => None,
+            })
+            .collect(),
+    };
+    candidates.retain(|p| rest.iter().all(|set| set.iter().any(|g| contains(g, *p))));
+    let mut unique: Vec<Vector> = Vec::new();
+    for p in candidates {
+        if unique.iter().all(|q| q.dist(p) > EPSILON) {
+            unique.push(p);
+        }
+    }
+    unique
+}
+
+/// Whether the shape contains the position.
+fn contains(g: &Geo, p: Vector) -> bool {
+    let tolerance = 1e-6;
+    match *g {
+        Geo::Point(v) => p.dist(v) < tolerance,
+        Geo::Circle(c, r) => (p.dist(c) - r).abs() < tolerance,
+        Geo::Line(o, d) => cross(d, p - o).abs() / length(d) < tolerance,
+        Geo::Ray(o, d) => {
+            cross(d, p - o).abs() / length(d) < tolerance && dot(d, p - o) > -tolerance
+        }
+    }
+}
+
+/// Intersects two shapes.
+fn intersect(a: &Geo, b: &Geo) -> Vec<Vector> {
+    match (a, b) {
+        (Geo::Point(v), g) | (g, Geo::Point(v)) => {
+            if contains(g, *v) {
+                vec![*v]
+            } else {
+                Vec::new()
+            }
+        }
+        (Geo::Circle(c0, r0), Geo::Circle(c1, r1)) => {
+            let d = c0.dist(*c1);
+            if d < EPSILON {
+                return Vec::new();
+            }
+            // Distance along and across the line between the centers.
+            let along = (r0 * r0 - r1 * r1 + d * d) / (2. * d);
+            let across = r0 * r0 - along * along;
+            if across < -EPSILON {
+                return Vec::new();
+            }
+            let across = across.max(0.).sqrt();
+            let u = scale(*c1 - *c0, 1. / d);
+            let m = offset(*c0, u, along);
+            let n = Vector { x: -u.y, y: u.x };
+            vec![offset(m, n, across), offset(m, n, -across)]
+        }
+        (Geo::Circle(c, r), line) | (line, Geo::Circle(c, r)) => {
+            let (o, d) = ends(line);
+            let u = scale(d, 1. / length(d));
+            let q = o - *c;
+            let b = dot(q, u);
+            let discriminant = b * b - (dot(q, q) - r * r);
+            if discriminant < -EPSILON {
+                return Vec::new();
+            }
+            let root = discriminant.max(0.).sqrt();
+            [-b - root, -b + root]
+                .into_iter()
+                .map(|t| offset(o, u, t))
+                .filter(|p| contains(line, *p))
+                .collect()
+        }
+        (l0, l1) => {
+            let (o0, d0) = ends(l0);
+            let (o1, d1) = ends(l1);
+            let denominator = cross(d0, d1);
+            if denominator.abs() < EPSILON {
+                return Vec::new();
+            }
+            let t = cross(o1 - o0, d1) / denominator;
+            let p = offset(o0, d0, t);
+            if contains(l0, p) && contains(l1, p) {
+                vec![p]
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// The origin and direction of a line or ray.
+fn ends(g: &Geo) -> (Vector, Vector) {
+    match *g {
+        Geo::Line(o, d) | Geo::Ray(o, d) => (o, d),
+        _ => unreachable!(),
+    }
+}
+
+fn cross(a: Vector, b: Vector) -> Number {
+    a.x * b.y - a.y * b.x
+}
+
+fn dot(a: Vector, b: Vector) -> Number {
+    a.x * b.x + a.y * b.y
+}
+
+fn length(v: Vector) -> Number {
+    v.dist(Vector::ZERO)
+}
+
+fn scale(v: Vector, s: Number) -> Vector {
+    Vector {
+        x: v.x * s,
+        y: v.y * s,
+    }
+}
+
+/// Moves `p` by `t` along `d`.
+fn offset(p: Vector, d: Vector, t: Number) -> Vector {
+    Vector {
+        x: p.x + d.x * t,
+        y: p.y + d.y * t,
+    }
+}